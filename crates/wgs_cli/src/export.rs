@@ -0,0 +1,109 @@
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use std::{fs, fs::File, io::BufReader, path::PathBuf};
+use wgs_core::{export_shader_with_source_map, ExportTarget, WgsData};
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// The wgs file to export.
+    input: PathBuf,
+    /// The shading language to export to.
+    #[arg(short, long)]
+    target: Target,
+    /// The directory to write the shaders to. Defaults to the current directory.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    Glsl,
+    Hlsl,
+    Msl,
+}
+
+impl Target {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Glsl => "glsl",
+            Self::Hlsl => "hlsl",
+            Self::Msl => "metal",
+        }
+    }
+}
+
+impl From<Target> for ExportTarget {
+    fn from(target: Target) -> Self {
+        match target {
+            Target::Glsl => Self::Glsl,
+            Target::Hlsl => Self::Hlsl,
+            Target::Msl => Self::Msl,
+        }
+    }
+}
+
+/// Writes `{stem}.vert.{ext}`, then `{stem}.frag.{ext}` along with `{stem}.{ext}.bindings.txt`
+/// for the image and `{stem}.{pass}.frag.{ext}` along with `{stem}.{pass}.{ext}.bindings.txt`
+/// for each buffer pass. The binding maps are named after the target, as their slots differ.
+pub fn run(args: ExportArgs) -> Result<()> {
+    let file = File::open(&args.input)
+        .with_context(|| format!("Failed to open {}", args.input.display()))?;
+
+    let wgs = WgsData::load(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to load {}", args.input.display()))?;
+
+    let stem = args
+        .input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "wgs".to_owned());
+
+    let output = args.output.unwrap_or_default();
+    if !output.as_os_str().is_empty() {
+        fs::create_dir_all(&output)
+            .with_context(|| format!("Failed to create {}", output.display()))?;
+    }
+
+    let mut shaders = vec![(stem.clone(), wgs.shader_frag()?)];
+    for (index, pass) in wgs.passes_ref().iter().enumerate() {
+        shaders.push((format!("{}.{}", stem, pass.name), wgs.shader_pass(index)?));
+    }
+
+    let extension = args.target.extension();
+    let target = args.target.into();
+
+    for (index, (name, (shader_frag, source_map))) in shaders.iter().enumerate() {
+        let export = match export_shader_with_source_map(shader_frag, source_map, target) {
+            Ok(export) => export,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{}: {}", args.input.display(), diagnostic);
+                }
+
+                bail!("Failed to export {}", name)
+            }
+        };
+
+        let bindings = format!("entry point: {}\n{}", export.fragment.entry_point, export);
+
+        let mut files = vec![
+            (
+                format!("{}.frag.{}", name, extension),
+                export.fragment.source,
+            ),
+            (format!("{}.{}.bindings.txt", name, extension), bindings),
+        ];
+
+        if index == 0 {
+            files.push((format!("{}.vert.{}", stem, extension), export.vertex.source));
+        }
+
+        for (file_name, contents) in files {
+            let path = output.join(file_name);
+            fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
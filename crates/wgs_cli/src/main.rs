@@ -0,0 +1,43 @@
+//! Command line tools for wgs files.
+
+mod export;
+mod pack;
+mod render;
+mod unpack;
+mod validate;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "wgs", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Exports the shaders of a wgs file to GLSL, HLSL or MSL, along with the bindings they expect.
+    Export(export::ExportArgs),
+    /// Packs a project directory into a wgs file.
+    Pack(pack::PackArgs),
+    /// Renders a wgs file to a PNG image without a window.
+    Render(render::RenderArgs),
+    /// Unpacks a wgs file into a project directory, which can be packed again.
+    Unpack(unpack::UnpackArgs),
+    /// Compiles the shaders of wgs files without a GPU and reports the errors.
+    Validate(validate::ValidateArgs),
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Export(args) => export::run(args),
+        Command::Pack(args) => pack::run(args),
+        Command::Render(args) => render::run(args),
+        Command::Unpack(args) => unpack::run(args),
+        Command::Validate(args) => validate::run(args),
+    }
+}
@@ -0,0 +1,28 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::{fs::File, io::BufWriter, path::PathBuf};
+use wgs_core::{WgsData, EXTENSION};
+
+#[derive(Args)]
+pub struct PackArgs {
+    /// The project directory to pack.
+    input: PathBuf,
+    /// The wgs file to write. Defaults to the directory with the `.wgs` extension.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: PackArgs) -> Result<()> {
+    let wgs = WgsData::from_dir(&args.input)
+        .with_context(|| format!("Failed to load {}", args.input.display()))?;
+
+    let output = args
+        .output
+        .unwrap_or_else(|| args.input.with_extension(EXTENSION));
+
+    let file =
+        File::create(&output).with_context(|| format!("Failed to create {}", output.display()))?;
+
+    wgs.save(&mut BufWriter::new(file))
+        .with_context(|| format!("Failed to save {}", output.display()))
+}
@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+use wgs_core::WgsData;
+use wgs_runtime_wgpu::{render_snapshot_png, Snapshot};
+
+#[derive(Args)]
+pub struct RenderArgs {
+    /// The wgs file to render.
+    input: PathBuf,
+    /// The PNG file to write.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// The width of the image in pixels.
+    #[arg(long, default_value_t = 512)]
+    width: u32,
+    /// The height of the image in pixels.
+    #[arg(long, default_value_t = 512)]
+    height: u32,
+    /// The elapsed time passed to the shader, in seconds.
+    #[arg(long, default_value_t = 0.0)]
+    time: f32,
+    /// The cursor position as `x,y`, with the origin at the top left corner.
+    #[arg(long, value_parser = parse_position)]
+    cursor: Option<[f32; 2]>,
+    /// Whether the left button of the mouse is down.
+    #[arg(long)]
+    mouse_down: bool,
+    /// The cursor position when the left button was pressed, as `x,y`.
+    #[arg(long, value_parser = parse_position)]
+    mouse_press: Option<[f32; 2]>,
+    /// The cursor position when the left button was released, as `x,y`.
+    #[arg(long, value_parser = parse_position)]
+    mouse_release: Option<[f32; 2]>,
+}
+
+pub fn run(args: RenderArgs) -> Result<()> {
+    let file = File::open(&args.input)
+        .with_context(|| format!("Failed to open {}", args.input.display()))?;
+
+    let wgs = WgsData::load(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to load {}", args.input.display()))?;
+
+    let mut snapshot = Snapshot::new(args.width, args.height);
+    snapshot.time = args.time;
+    snapshot.mouse_down = args.mouse_down;
+
+    if let Some(cursor) = args.cursor {
+        snapshot.cursor = cursor;
+    }
+
+    if let Some(mouse_press) = args.mouse_press {
+        snapshot.mouse_press = mouse_press;
+    }
+
+    if let Some(mouse_release) = args.mouse_release {
+        snapshot.mouse_release = mouse_release;
+    }
+
+    let output = File::create(&args.output)
+        .with_context(|| format!("Failed to create {}", args.output.display()))?;
+
+    futures::executor::block_on(render_snapshot_png(wgs, &snapshot, BufWriter::new(output)))
+}
+
+fn parse_position(s: &str) -> Result<[f32; 2], String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected `x,y`, found `{}`", s))?;
+
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<f32>()
+            .map_err(|err| format!("invalid coordinate `{}`: {}", v, err))
+    };
+
+    Ok([parse(x)?, parse(y)?])
+}
@@ -0,0 +1,26 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::{fs::File, io::BufReader, path::PathBuf};
+use wgs_core::WgsData;
+
+#[derive(Args)]
+pub struct UnpackArgs {
+    /// The wgs file to unpack.
+    input: PathBuf,
+    /// The project directory to write. Defaults to the file without its extension.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: UnpackArgs) -> Result<()> {
+    let file = File::open(&args.input)
+        .with_context(|| format!("Failed to open {}", args.input.display()))?;
+
+    let wgs = WgsData::load(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to load {}", args.input.display()))?;
+
+    let output = args.output.unwrap_or_else(|| args.input.with_extension(""));
+
+    wgs.to_dir(&output)
+        .with_context(|| format!("Failed to write {}", output.display()))
+}
@@ -0,0 +1,41 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use std::{fs::File, io::BufReader, path::PathBuf};
+use wgs_core::WgsData;
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    /// The wgs files to validate.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+}
+
+pub fn run(args: ValidateArgs) -> Result<()> {
+    let mut failed = 0;
+
+    for input in &args.inputs {
+        let file =
+            File::open(input).with_context(|| format!("Failed to open {}", input.display()))?;
+
+        let wgs = WgsData::load(&mut BufReader::new(file))
+            .with_context(|| format!("Failed to load {}", input.display()))?;
+
+        if let Err(diagnostics) = wgs.validate() {
+            failed += 1;
+
+            for diagnostic in diagnostics {
+                eprintln!("{}: {}", input.display(), diagnostic);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} of {} files failed to validate",
+            failed,
+            args.inputs.len()
+        )
+    }
+
+    Ok(())
+}
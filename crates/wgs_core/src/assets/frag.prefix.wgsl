@@ -9,6 +9,10 @@ struct Uniforms {
 
 @group(0) @binding(0)
 var<uniform> u: Uniforms;
+@group(0) @binding(1)
+var previous_frame: texture_2d<f32>;
+@group(0) @binding(2)
+var previous_frame_sampler: sampler;
 
 fn image(t: texture_2d<f32>, spl: sampler, uv: vec2<f32>) -> vec4<f32> {
     return textureSample(t, spl, vec2(uv.x, 1.0 - uv.y));
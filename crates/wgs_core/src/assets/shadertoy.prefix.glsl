@@ -0,0 +1,50 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform Uniforms {
+    vec2 cursor;
+    uint mouse_down;
+    vec2 mouse_press;
+    vec2 mouse_release;
+    vec2 resolution;
+    float time;
+} u;
+
+layout(set = 1, binding = 0) uniform texture2D texture0;
+layout(set = 1, binding = 1) uniform sampler sampler0;
+layout(set = 2, binding = 0) uniform texture2D texture1;
+layout(set = 2, binding = 1) uniform sampler sampler1;
+layout(set = 3, binding = 0) uniform texture2D texture2;
+layout(set = 3, binding = 1) uniform sampler sampler2;
+layout(set = 4, binding = 0) uniform texture2D texture3;
+layout(set = 4, binding = 1) uniform sampler sampler3;
+
+// The textures of Shadertoy are flipped vertically, like the `image` function of wgs.
+vec4 wgs_texture(texture2D t, sampler s, vec2 uv) {
+    return texture(sampler2D(t, s), vec2(uv.x, 1.0 - uv.y));
+}
+
+vec4 wgs_texture_lod(texture2D t, sampler s, vec2 uv, float lod) {
+    return textureLod(sampler2D(t, s), vec2(uv.x, 1.0 - uv.y), lod);
+}
+
+vec4 wgs_texel_fetch(texture2D t, sampler s, ivec2 coord, int lod) {
+    ivec2 size = textureSize(sampler2D(t, s), lod);
+    return texelFetch(sampler2D(t, s), ivec2(coord.x, size.y - 1 - coord.y), lod);
+}
+
+ivec2 wgs_texture_size(texture2D t, sampler s, int lod) {
+    return textureSize(sampler2D(t, s), lod);
+}
+
+#define iResolution vec3(u.resolution, 1.0)
+#define iTime u.time
+#define iMouse vec4(u.cursor, u.mouse_down != 0u ? u.mouse_press.x : -u.mouse_press.x, -u.mouse_press.y)
+#define iChannel0 texture0, sampler0
+#define iChannel1 texture1, sampler1
+#define iChannel2 texture2, sampler2
+#define iChannel3 texture3, sampler3
+#define texture(s, uv) wgs_texture(s, uv)
+#define textureLod(s, uv, lod) wgs_texture_lod(s, uv, lod)
+#define texelFetch(s, coord, lod) wgs_texel_fetch(s, coord, lod)
+#define textureSize(s, lod) wgs_texture_size(s, lod)
+
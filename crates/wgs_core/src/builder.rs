@@ -0,0 +1,124 @@
+use crate::{param::check_params, Error, Library, Param, Pass, Texture, WgsData, FRAG_DEFAULT};
+
+/// Builds [`WgsData`] step by step, checking the whole of it at the end.
+///
+/// The frag defaults to [`FRAG_DEFAULT`].
+pub struct WgsDataBuilder {
+    wgs: WgsData,
+    textures: Vec<Texture>,
+}
+
+impl WgsDataBuilder {
+    /// Starts building wgs data of the given name.
+    pub fn new(name: &str) -> Self {
+        Self {
+            wgs: WgsData::new(name, FRAG_DEFAULT),
+            textures: vec![],
+        }
+    }
+
+    /// Sets the author.
+    pub fn author(mut self, author: &str) -> Self {
+        self.wgs.set_author(author);
+        self
+    }
+
+    /// Builds the wgs data.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::TooManyTextures`] if there are more than 255 textures.
+    /// - Will return [`Error::SizeMismatch`] if the data of a texture is not 8bit RGBA of its size.
+    /// - Will return [`Error::InvalidTexture`] if the name of a texture is not an identifier
+    ///   or is taken.
+    /// - Will return [`Error::InvalidParam`] if a parameter is invalid, for example two of them
+    ///   have the same name or the range of a float is empty.
+    /// - Will return [`Error::InvalidPasses`] if the buffer passes can't be ordered,
+    ///   see [`WgsData::pass_order`].
+    pub fn build(self) -> Result<WgsData, Error> {
+        let mut wgs = self.wgs;
+
+        for texture in self.textures {
+            wgs.insert_texture(wgs.textures_ref().len(), texture)?;
+        }
+
+        check_params(wgs.params_ref())?;
+
+        wgs.pass_order()?;
+
+        Ok(wgs)
+    }
+
+    /// Sets when the wgs data was created, in seconds since the Unix epoch.
+    pub fn created(mut self, created: u64) -> Self {
+        self.wgs.set_created(Some(created));
+        self
+    }
+
+    /// Sets the description.
+    pub fn description(mut self, description: &str) -> Self {
+        self.wgs.set_description(description);
+        self
+    }
+
+    /// Sets the content of the editable part of the fragment shader.
+    pub fn frag(mut self, frag: &str) -> Self {
+        self.wgs.set_frag(frag);
+        self
+    }
+
+    /// Sets the WGSL modules the frags can `#include`.
+    pub fn library(mut self, library: Library) -> Self {
+        self.wgs.set_library(library);
+        self
+    }
+
+    /// Sets the license, preferably an SPDX license identifier.
+    pub fn license(mut self, license: &str) -> Self {
+        self.wgs.set_license(license);
+        self
+    }
+
+    /// Sets when the wgs data was last modified, in seconds since the Unix epoch.
+    pub fn modified(mut self, modified: u64) -> Self {
+        self.wgs.set_modified(Some(modified));
+        self
+    }
+
+    /// Adds a parameter.
+    pub fn param(mut self, param: Param) -> Self {
+        self.wgs.add_param(param);
+        self
+    }
+
+    /// Adds a buffer pass.
+    pub fn pass(mut self, pass: Pass) -> Self {
+        self.wgs.add_pass(pass);
+        self
+    }
+
+    /// Sets the URL of the source the wgs data comes from.
+    pub fn source_url(mut self, source_url: &str) -> Self {
+        self.wgs.set_source_url(source_url);
+        self
+    }
+
+    /// Sets the free-form tags.
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.wgs.set_tags(tags);
+        self
+    }
+
+    /// Adds a texture, which is declared after its [`Texture::name`], or as `texture{index}`
+    /// in the order of the calls if it has no name.
+    pub fn texture(mut self, texture: Texture) -> Self {
+        self.textures.push(texture);
+        self
+    }
+
+    /// Sets the preview image.
+    pub fn thumbnail(mut self, thumbnail: Texture) -> Self {
+        self.wgs.set_thumbnail(Some(thumbnail));
+        self
+    }
+}
@@ -0,0 +1,86 @@
+use crate::chunk::{Chunk, MAGIC};
+use std::fmt;
+
+/// The error returned when the checksum of a wgs file doesn't match its contents,
+/// which usually means the file is truncated or corrupted.
+///
+/// Returned as [`Error::Checksum`](crate::Error::Checksum).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChecksumError {
+    /// The checksum stored in the file.
+    pub expected: u32,
+    /// The checksum of the contents read.
+    pub found: u32,
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checksum mismatch: expected {:#010x}, found {:#010x}. The wgs file may be truncated or corrupted",
+            self.expected, self.found
+        )
+    }
+}
+
+/// Computes the CRC-32 of the magic bytes, the version and the chunks, as laid out in the file.
+pub(crate) fn checksum<'a>(version: u32, chunks: impl IntoIterator<Item = &'a Chunk>) -> u32 {
+    let mut crc = Crc32::default();
+    crc.update(&MAGIC);
+    crc.update(&version.to_le_bytes());
+
+    for chunk in chunks {
+        crc.update(&chunk.tag);
+        crc.update(&(chunk.data.len() as u32).to_le_bytes());
+        crc.update(&chunk.data);
+    }
+
+    crc.finish()
+}
+
+/// CRC-32 with the polynomial of zlib and PNG.
+pub(crate) struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self(0xffff_ffff)
+    }
+}
+
+impl Crc32 {
+    pub fn finish(self) -> u32 {
+        !self.0
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u32;
+            for _ in 0..8 {
+                self.0 = (self.0 >> 1) ^ (0xedb8_8320 & (self.0 & 1).wrapping_neg());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_zlib() {
+        let mut crc = Crc32::default();
+        crc.update(b"123456789");
+
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn checksum_covers_every_chunk() {
+        let chunks = [Chunk::new(*b"FRAG", b"fn".to_vec())];
+        let changed = [Chunk::new(*b"FRAG", b"fm".to_vec())];
+
+        assert_eq!(checksum(2, &chunks), checksum(2, &chunks));
+        assert_ne!(checksum(2, &chunks), checksum(2, &changed));
+        assert_ne!(checksum(2, &chunks), checksum(3, &chunks));
+    }
+}
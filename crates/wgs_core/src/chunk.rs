@@ -0,0 +1,350 @@
+use crate::{Error, VERSION};
+use binrw::{binrw, helpers::until, BinRead, BinResult, BinWrite, NullString};
+use std::io;
+
+/// The magic bytes of a wgs file since wgs 2.
+pub const MAGIC: [u8; 4] = *b"\0WGS";
+
+/// Holds the meta info. See [`MetaChunk`].
+pub const META: [u8; 4] = *b"META";
+/// Holds the descriptive info. See [`InfoChunk`]. Optional.
+pub const INFO: [u8; 4] = *b"INFO";
+/// Holds the editable part of the fragment shader, in UTF-8 without a trailing null byte.
+pub const FRAG: [u8; 4] = *b"FRAG";
+/// Holds a texture. See [`TextureChunk`]. Appears once per texture, in order.
+pub const TEXTURE: [u8; 4] = *b"TXTR";
+/// Holds the preview image. Same payload as a [`TEXTURE`] chunk, without the sampler. Optional.
+pub const THUMBNAIL: [u8; 4] = *b"THMB";
+/// Holds a buffer pass. See [`PassChunk`]. Appears once per pass, in order.
+pub const PASS: [u8; 4] = *b"PASS";
+/// Holds a parameter. See [`ParamChunk`]. Appears once per parameter, in order.
+pub const PARAM: [u8; 4] = *b"PARM";
+/// Holds a module of the library. See [`IncludeChunk`]. Appears once per module.
+pub const INCLUDE: [u8; 4] = *b"INCL";
+/// Holds the CRC-32 of everything before it, as a `u32`. Comes right before the [`END`] chunk.
+/// Optional, but always written.
+pub const CHECKSUM: [u8; 4] = *b"CSUM";
+/// Marks the end of the chunks. Has no payload.
+pub const END: [u8; 4] = *b"END\0";
+
+/// The layout of a wgs file since wgs 2.
+///
+/// Starts with the magic bytes `\0WGS`. The leading null byte keeps it from being confused
+/// with the name at the beginning of a wgs 1 file.
+///
+/// The body is a list of tagged, length-prefixed chunks terminated by an [`END`] chunk.
+/// Readers skip the chunks they don't know, so new data can be added in new chunks
+/// without breaking existing readers.
+///
+/// The payload of a chunk may also grow new fields at its end in later versions,
+/// thus readers must ignore any trailing bytes of a payload they have parsed.
+#[binrw]
+#[brw(little, magic = b"\0WGS")]
+pub struct Container {
+    #[br(assert(version == VERSION, Error::UnsupportedVersion(version)))]
+    pub version: u32,
+    #[br(parse_with = until(|chunk: &Chunk| chunk.tag == END))]
+    pub chunks: Vec<Chunk>,
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Chunk {
+    /// The position of the chunk in the stream. Only meaningful for chunks that have been read.
+    #[br(parse_with = stream_position)]
+    #[bw(ignore)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub pos: u64,
+    pub tag: [u8; 4],
+    #[br(temp)]
+    #[bw(try_calc(u32::try_from(data.len())))]
+    len: u32,
+    #[br(parse_with = read_bytes, args(Some(len.into())))]
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+    pub data: Vec<u8>,
+}
+
+/// The payload of a [`META`] chunk.
+#[binrw]
+#[brw(little)]
+pub struct MetaChunk {
+    /// The name of the wgs file. Not filename.
+    pub name: NullString,
+}
+
+/// The payload of an [`INFO`] chunk.
+#[binrw]
+#[brw(little)]
+pub struct InfoChunk {
+    pub author: NullString,
+    pub description: NullString,
+    /// An SPDX license identifier, preferably.
+    pub license: NullString,
+    pub source_url: NullString,
+    #[br(temp)]
+    #[bw(try_calc(u32::try_from(tags.len())))]
+    tag_count: u32,
+    #[br(count = tag_count)]
+    pub tags: Vec<NullString>,
+    /// In seconds since the Unix epoch.
+    pub created: u64,
+    /// In seconds since the Unix epoch.
+    pub modified: u64,
+    /// Which timestamps are known, see [`Self::CREATED`] and [`Self::MODIFIED`].
+    /// If absent, a timestamp of `0` is unknown.
+    #[br(try)]
+    pub timestamps: Option<u8>,
+}
+
+impl InfoChunk {
+    /// The bit of [`Self::timestamps`] set if [`Self::created`] is known.
+    pub const CREATED: u8 = 1;
+    /// The bit of [`Self::timestamps`] set if [`Self::modified`] is known.
+    pub const MODIFIED: u8 = 2;
+
+    /// Returns the timestamp if the bit says it's known.
+    pub fn timestamp(&self, timestamp: u64, bit: u8) -> Option<u64> {
+        match self.timestamps {
+            Some(timestamps) => (timestamps & bit != 0).then_some(timestamp),
+            None => (timestamp != 0).then_some(timestamp),
+        }
+    }
+}
+
+/// The payload of a [`TEXTURE`] or a [`THUMBNAIL`] chunk.
+#[binrw]
+#[brw(little)]
+pub struct TextureChunk {
+    pub width: u32,
+    pub height: u32,
+    #[br(temp)]
+    #[bw(try_calc(u32::try_from(data.len())))]
+    len: u32,
+    /// The pixel data of the texture, encoded as [`Self::encoding`].
+    #[br(count = len)]
+    pub data: Vec<u8>,
+    /// The [`TextureEncoding`](crate::TextureEncoding) of the data. Raw if absent.
+    #[br(try)]
+    pub encoding: Option<u8>,
+    /// The [`TextureSampler`](crate::TextureSampler) of the texture. The default one if absent.
+    #[br(try)]
+    pub sampler: Option<[u8; 4]>,
+    /// The [`Texture::name`](crate::Texture::name) of the texture. Unnamed if absent.
+    #[br(try)]
+    pub name: Option<NullString>,
+}
+
+/// The payload of a [`PASS`] chunk.
+#[binrw]
+#[brw(little)]
+pub struct PassChunk {
+    pub name: NullString,
+    #[br(temp)]
+    #[bw(try_calc(u32::try_from(frag.len())))]
+    frag_len: u32,
+    /// The frag of the pass in UTF-8.
+    #[br(count = frag_len)]
+    pub frag: Vec<u8>,
+    #[br(temp)]
+    #[bw(try_calc(u32::try_from(inputs.len())))]
+    input_count: u32,
+    #[br(count = input_count)]
+    pub inputs: Vec<PassInputChunk>,
+}
+
+/// An input of a [`PassChunk`].
+#[binrw]
+#[brw(little)]
+pub enum PassInputChunk {
+    /// The index of a texture.
+    #[brw(magic = 0u8)]
+    Texture(u32),
+    /// The name of a pass.
+    #[brw(magic = 1u8)]
+    Pass(NullString),
+}
+
+/// The payload of an [`INCLUDE`] chunk.
+#[binrw]
+#[brw(little)]
+pub struct IncludeChunk {
+    pub name: NullString,
+    #[br(temp)]
+    #[bw(try_calc(u32::try_from(source.len())))]
+    source_len: u32,
+    /// The WGSL source of the module in UTF-8.
+    #[br(count = source_len)]
+    pub source: Vec<u8>,
+}
+
+/// The payload of a [`PARAM`] chunk.
+#[binrw]
+#[brw(little)]
+pub struct ParamChunk {
+    pub name: NullString,
+    pub kind: ParamKindChunk,
+}
+
+/// The type of a [`ParamChunk`], followed by its default value and constraints.
+#[binrw]
+#[brw(little)]
+pub enum ParamKindChunk {
+    #[brw(magic = 0u8)]
+    Bool { default: u8 },
+    #[brw(magic = 1u8)]
+    Color { default: [f32; 3] },
+    #[brw(magic = 2u8)]
+    Enum {
+        default: u32,
+        #[br(temp)]
+        #[bw(try_calc(u32::try_from(options.len())))]
+        option_count: u32,
+        #[br(count = option_count)]
+        options: Vec<NullString>,
+    },
+    #[brw(magic = 3u8)]
+    Float { default: f32, min: f32, max: f32 },
+}
+
+impl Chunk {
+    pub fn new(tag: [u8; 4], data: Vec<u8>) -> Self {
+        Self { pos: 0, tag, data }
+    }
+
+    /// Creates a chunk holding the payload.
+    pub fn with_payload<T>(tag: [u8; 4], payload: &T) -> BinResult<Self>
+    where
+        T: for<'a> BinWrite<Args<'a> = ()>,
+    {
+        let mut cursor = io::Cursor::new(vec![]);
+        payload.write_le(&mut cursor)?;
+
+        Ok(Self::new(tag, cursor.into_inner()))
+    }
+
+    /// Parses the payload of the chunk. Trailing bytes of the payload are ignored.
+    pub fn payload<T>(&self) -> BinResult<T>
+    where
+        T: for<'a> BinRead<Args<'a> = ()>,
+    {
+        T::read_le(&mut io::Cursor::new(&self.data)).map_err(|err| binrw::Error::Custom {
+            pos: self.pos,
+            err: Box::new(format!(
+                "Invalid {} chunk: {}",
+                String::from_utf8_lossy(&self.tag),
+                err.root_cause()
+            )),
+        })
+    }
+}
+
+/// Reads the given number of bytes, `None` meaning more than addressable.
+///
+/// Fails with an end-of-file error without allocating
+/// if the reader doesn't have that many bytes left.
+#[binrw::parser(reader)]
+pub(crate) fn read_bytes(len: Option<u64>) -> BinResult<Vec<u8>> {
+    let position = reader.stream_position()?;
+    let end = reader.seek(io::SeekFrom::End(0))?;
+    reader.seek(io::SeekFrom::Start(position))?;
+
+    let len = len
+        .filter(|len| *len <= end.saturating_sub(position))
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    let mut data = vec![0; len as usize];
+    reader.read_exact(&mut data)?;
+
+    Ok(data)
+}
+
+#[binrw::parser(reader)]
+fn stream_position() -> BinResult<u64> {
+    Ok(reader.stream_position()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    fn write(container: &Container) -> Vec<u8> {
+        let mut cursor = io::Cursor::new(vec![]);
+        container.write(&mut cursor).unwrap();
+
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn container_round_trips() {
+        let container = Container {
+            version: VERSION,
+            chunks: vec![Chunk::new(*b"XTRA", vec![1, 2, 3]), Chunk::new(END, vec![])],
+        };
+
+        let read = Container::read(&mut io::Cursor::new(write(&container))).unwrap();
+        assert_eq!(read.version, VERSION);
+        assert_eq!(
+            read.chunks
+                .iter()
+                .map(|chunk| (chunk.tag, chunk.data.clone()))
+                .collect::<Vec<_>>(),
+            [(*b"XTRA", vec![1, 2, 3]), (END, vec![])]
+        );
+    }
+
+    #[test]
+    fn missing_end_is_truncated() {
+        let container = Container {
+            version: VERSION,
+            chunks: vec![Chunk::new(*b"XTRA", vec![1, 2, 3])],
+        };
+
+        let err = Container::read(&mut io::Cursor::new(write(&container)))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(matches!(Error::from(err), Error::Truncated));
+    }
+
+    #[test]
+    fn chunk_longer_than_the_file_is_truncated() {
+        let mut file = write(&Container {
+            version: VERSION,
+            chunks: vec![],
+        });
+        file.extend(b"XTRA");
+        file.extend(u32::MAX.to_le_bytes());
+        file.extend([0; 16]);
+
+        let err = Container::read(&mut io::Cursor::new(file))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(matches!(Error::from(err), Error::Truncated));
+    }
+
+    #[test]
+    fn other_version_is_unsupported() {
+        let file = write(&Container {
+            version: VERSION + 1,
+            chunks: vec![Chunk::new(END, vec![])],
+        });
+
+        let err = Container::read(&mut io::Cursor::new(file))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(
+            matches!(Error::from(err), Error::UnsupportedVersion(version) if version == VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn payload_ignores_trailing_bytes() {
+        let mut chunk = Chunk::with_payload(META, &MetaChunk { name: "a".into() }).unwrap();
+        chunk.data.extend([1, 2, 3]);
+
+        let meta: MetaChunk = chunk.payload().unwrap();
+        assert_eq!(meta.name.to_string(), "a");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::texture::TextureEncoding;
+
+/// Encodes 8bit RGBA pixel data.
+#[cfg_attr(not(any(feature = "png", feature = "qoi")), allow(unused_variables))]
+pub fn encode(
+    encoding: TextureEncoding,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<Vec<u8>, String> {
+    match encoding {
+        TextureEncoding::Raw => Ok(data.to_vec()),
+        #[cfg(feature = "png")]
+        TextureEncoding::Png => encode_png(width, height, data),
+        #[cfg(feature = "qoi")]
+        TextureEncoding::Qoi => {
+            qoi::encode_to_vec(data, width, height).map_err(|err| err.to_string())
+        }
+        #[cfg(feature = "zstd")]
+        TextureEncoding::Zstd => zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+            .map_err(|err| err.to_string()),
+        #[allow(unreachable_patterns)]
+        _ => Err(unsupported(encoding)),
+    }
+}
+
+/// Decodes the encoded data of a texture to 8bit RGBA pixel data.
+pub fn decode(
+    encoding: TextureEncoding,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    let expected_len = texture_len(width, height)
+        .ok_or_else(|| format!("Texture of {}x{} is too large", width, height))?;
+
+    let decoded = match encoding {
+        TextureEncoding::Raw => data,
+        #[cfg(feature = "png")]
+        TextureEncoding::Png => decode_png(&data, Some((width, height)))?.2,
+        #[cfg(feature = "qoi")]
+        TextureEncoding::Qoi => {
+            let decoder = qoi::Decoder::new(&data).map_err(|err| err.to_string())?;
+
+            // Checked before decoding, so that the header can't make it allocate more.
+            let header = decoder.header();
+            if (header.width, header.height) != (width, height) {
+                return Err(format!(
+                    "QOI of {}x{} found in texture of {}x{}",
+                    header.width, header.height, width, height
+                ));
+            }
+
+            decoder
+                .with_channels(qoi::Channels::Rgba)
+                .decode_to_vec()
+                .map_err(|err| err.to_string())?
+        }
+        #[cfg(feature = "zstd")]
+        TextureEncoding::Zstd => {
+            let capacity = usize::try_from(expected_len).map_err(|err| err.to_string())?;
+            zstd::bulk::decompress(&data, capacity).map_err(|err| err.to_string())?
+        }
+        #[allow(unreachable_patterns)]
+        _ => return Err(unsupported(encoding)),
+    };
+
+    if decoded.len() as u64 != expected_len {
+        return Err(format!(
+            "Texture of {}x{} expects {} bytes, found {}",
+            width,
+            height,
+            expected_len,
+            decoded.len()
+        ));
+    }
+
+    Ok(decoded)
+}
+
+/// Decodes a PNG image of any color type to 8bit RGBA pixel data.
+///
+/// The image must be of the given size, if any. Returns the width, the height and the pixel data.
+#[cfg(feature = "png")]
+pub fn decode_png(data: &[u8], size: Option<(u32, u32)>) -> Result<(u32, u32, Vec<u8>), String> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+
+    // Checked before decoding, so that the header can't make it allocate more.
+    if let Some((width, height)) = size {
+        let (png_width, png_height) = reader.info().size();
+        if (png_width, png_height) != (width, height) {
+            return Err(format!(
+                "PNG of {}x{} found in texture of {}x{}",
+                png_width, png_height, width, height
+            ));
+        }
+    }
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|err| err.to_string())?;
+    buffer.truncate(info.buffer_size());
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, u8::MAX]).collect(),
+        png::ColorType::Indexed => return Err("Unexpanded indexed PNG".to_owned()),
+    };
+
+    Ok((info.width, info.height, data))
+}
+
+/// Encodes 8bit RGBA pixel data as a PNG image.
+#[cfg(feature = "png")]
+pub fn encode_png(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut output = vec![];
+
+    let mut encoder = png::Encoder::new(&mut output, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+    writer
+        .write_image_data(data)
+        .map_err(|err| err.to_string())?;
+    writer.finish().map_err(|err| err.to_string())?;
+
+    Ok(output)
+}
+
+#[allow(dead_code)]
+fn unsupported(encoding: TextureEncoding) -> String {
+    format!(
+        "Texture encoding {:?} requires the `{}` feature of wgs_core",
+        encoding,
+        format!("{:?}", encoding).to_lowercase()
+    )
+}
+
+/// Returns the length of the 8bit RGBA pixel data of a texture. `None` on overflow.
+pub fn texture_len(width: u32, height: u32) -> Option<u64> {
+    (width as u64)
+        .checked_mul(height as u64)
+        .and_then(|len| len.checked_mul(4))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The encodings enabled by the cargo features.
+    pub(crate) fn encodings() -> Vec<TextureEncoding> {
+        [
+            (TextureEncoding::Raw, true),
+            (TextureEncoding::Png, cfg!(feature = "png")),
+            (TextureEncoding::Qoi, cfg!(feature = "qoi")),
+            (TextureEncoding::Zstd, cfg!(feature = "zstd")),
+        ]
+        .into_iter()
+        .filter(|(_, is_enabled)| *is_enabled)
+        .map(|(encoding, _)| encoding)
+        .collect()
+    }
+
+    /// Rewrites the size in the header of a PNG image, keeping the CRC of the header valid.
+    #[cfg(feature = "png")]
+    pub(crate) fn resize_png_header(mut png: Vec<u8>, width: u32, height: u32) -> Vec<u8> {
+        use crate::checksum::Crc32;
+
+        png[16..20].copy_from_slice(&width.to_be_bytes());
+        png[20..24].copy_from_slice(&height.to_be_bytes());
+
+        let mut crc = Crc32::default();
+        crc.update(&png[12..29]);
+        png[29..33].copy_from_slice(&crc.finish().to_be_bytes());
+
+        png
+    }
+
+    #[test]
+    fn each_encoding_round_trips() {
+        let data: Vec<u8> = (0..3 * 2 * 4).map(|byte| byte * 10).collect();
+
+        for encoding in encodings() {
+            let encoded = encode(encoding, 3, 2, &data).unwrap();
+            assert_eq!(
+                decode(encoding, 3, 2, encoded).unwrap(),
+                data,
+                "{:?}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn data_of_another_size_is_rejected() {
+        for encoding in encodings() {
+            let encoded = encode(encoding, 2, 2, &[0; 16]).unwrap();
+            assert!(decode(encoding, 1, 1, encoded).is_err(), "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn oversized_texture_is_rejected() {
+        assert!(decode(TextureEncoding::Raw, u32::MAX, u32::MAX, vec![]).is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_header_of_another_size_is_rejected() {
+        let png = encode_png(1, 1, &[1, 2, 3, 4]).unwrap();
+        let png = resize_png_header(png, 20000, 20000);
+
+        let err = decode(TextureEncoding::Png, 1, 1, png).unwrap_err();
+        assert!(err.contains("PNG of 20000x20000"), "{}", err);
+    }
+
+    #[cfg(feature = "qoi")]
+    #[test]
+    fn qoi_header_of_another_size_is_rejected() {
+        let mut qoi = encode(TextureEncoding::Qoi, 1, 1, &[1, 2, 3, 4]).unwrap();
+        qoi[4..8].copy_from_slice(&20000u32.to_be_bytes());
+        qoi[8..12].copy_from_slice(&20000u32.to_be_bytes());
+
+        let err = decode(TextureEncoding::Qoi, 1, 1, qoi).unwrap_err();
+        assert!(err.contains("QOI of 20000x20000"), "{}", err);
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn disabled_encoding_is_unsupported() {
+        let err = decode(TextureEncoding::Zstd, 1, 1, vec![0; 4]).unwrap_err();
+        assert!(err.contains("`zstd` feature"), "{}", err);
+    }
+}
@@ -1,16 +1,72 @@
-use crate::{meta::Meta, texture::Texture, FRAG_DEFAULT};
-use binrw::{binrw, BinRead, BinResult, BinWrite, NullString};
+#[cfg(feature = "dir")]
+use crate::dir;
+#[cfg(feature = "validate")]
+use crate::validate::Diagnostic;
+use crate::{
+    assemble_shader,
+    checksum::{checksum, ChecksumError},
+    chunk::{
+        self, Chunk, Container, IncludeChunk, InfoChunk, MetaChunk, ParamChunk, ParamKindChunk,
+        PassChunk, PassInputChunk, TextureChunk,
+    },
+    codec, declare_inputs, declare_textures,
+    error::Error,
+    include::Library,
+    legacy::{load_legacy, Migration},
+    meta::{Meta, Metadata},
+    options::{Budget, LimitError, LoadOptions},
+    param::{check_params, declare_params, Param, ParamKind},
+    pass::{sort_passes, Pass, PassInput},
+    preprocess::{builtin_defines, preprocess},
+    sampler::TextureSampler,
+    texture::{check_texture_names, Texture, TextureEncoding},
+    v1::WgsDataV1,
+    SourceMap, SourceOrigin, FRAG_DEFAULT, FRAG_PASS_SUFFIX, FRAG_SUFFIX, VERSION,
+};
+use binrw::{BinRead, BinWrite, NullString};
+#[cfg(any(feature = "dir", feature = "validate"))]
+use std::collections::HashSet;
 use std::io;
+#[cfg(feature = "dir")]
+use std::path::Path;
+
+/// The maximum number of textures, as the count is stored in a `u8` in wgs 1.
+const MAX_TEXTURE_COUNT: usize = u8::MAX as usize;
 
 /// The core implementation of a wgs file.
+///
+/// With the cargo feature `serde`, it can also be serialized to a text document,
+/// which loads back to the same data. Textures are stored in their encoding, as base64.
 #[derive(Debug)]
-#[binrw]
-#[brw(little)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct WgsData {
     meta: Meta,
+    #[cfg_attr(feature = "serde", serde(default))]
+    metadata: Metadata,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::null_string"))]
     frag: NullString,
-    #[br(count = meta.texture_count)]
+    #[cfg_attr(feature = "serde", serde(default))]
     textures: Vec<Texture>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    params: Vec<Param>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    passes: Vec<Pass>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Library::is_empty")
+    )]
+    library: Library,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    thumbnail: Option<Texture>,
+    /// The chunks unknown to this version, kept as they are so that saving won't lose them.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    unknown_chunks: Vec<Chunk>,
 }
 
 impl Default for WgsData {
@@ -20,9 +76,152 @@ impl Default for WgsData {
 }
 
 impl WgsData {
-    /// Loads wgs data from a reader. [Read more](binrw::BinRead::read).
-    pub fn load(reader: &mut (impl io::Read + io::Seek)) -> BinResult<Self> {
-        Self::read(reader)
+    /// Loads wgs data from a project directory written by [`Self::to_dir`],
+    /// which may have been edited since.
+    ///
+    /// Requires the cargo feature `dir`.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::Io`] if a file can't be read.
+    /// - Will return [`Error::Parse`] if `manifest.toml` is malformed.
+    /// - Will return [`Error::Codec`] if a texture is not a PNG image.
+    /// - Will return [`Error::InvalidParam`] if a parameter is invalid.
+    #[cfg(feature = "dir")]
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let dir = path.as_ref();
+        let manifest = dir::read_manifest(dir)?;
+
+        if manifest.textures.len() > MAX_TEXTURE_COUNT {
+            return Err(Error::TooManyTextures {
+                max: MAX_TEXTURE_COUNT,
+            });
+        }
+
+        let mut wgs = Self::new(&manifest.name, "");
+        wgs.metadata = manifest.metadata;
+        wgs.frag = NullString(dir::read(dir, dir::FRAG)?);
+        std::str::from_utf8(&wgs.frag)?;
+
+        for texture in &manifest.textures {
+            wgs.textures.push(texture.read(dir)?);
+        }
+
+        wgs.meta.texture_count = wgs.textures.len() as u8;
+
+        if let Some(thumbnail) = &manifest.thumbnail {
+            wgs.thumbnail = Some(thumbnail.read(dir)?);
+        }
+
+        wgs.params = manifest.params;
+        check_params(&wgs.params)?;
+
+        for pass in manifest.passes {
+            let frag =
+                String::from_utf8(dir::read(dir, &pass.path)?).map_err(|err| err.utf8_error())?;
+
+            wgs.passes.push(Pass {
+                name: pass.name,
+                frag,
+                inputs: pass.inputs,
+            });
+        }
+
+        for include in manifest.includes {
+            let source = dir::read(dir, &include.path)?;
+            wgs.library
+                .insert(&include.name, std::str::from_utf8(&source)?);
+        }
+
+        wgs.unknown_chunks = manifest.unknown_chunks;
+
+        Ok(wgs)
+    }
+
+    /// Loads wgs data from a reader.
+    ///
+    /// Reads both the chunked layout of wgs 2 and the fixed layout of wgs 1.
+    /// Legacy wgs data without the `version` field is detected and upgraded in memory.
+    /// See [`Self::load_legacy`].
+    ///
+    /// The checksum of the file is verified, see [`Self::load_with_options`].
+    pub fn load(reader: &mut (impl io::Read + io::Seek)) -> Result<Self, Error> {
+        Self::load_with_options(reader, &LoadOptions::default())
+    }
+
+    /// Loads wgs data from a reader like [`Self::load`], with the given options.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::Truncated`] if the file ends before its `END` chunk.
+    /// - Will return [`Error::Limit`] if the file exceeds one of the limits of the options.
+    /// - Will return [`Error::Checksum`] if the file has a checksum which doesn't match its
+    ///   contents and [`LoadOptions::verify_checksum`] is on.
+    /// - Will return [`Error::InvalidUtf8`] if a frag is not UTF-8.
+    /// - Will return [`Error::InvalidParam`] if a parameter is invalid, for example two of them
+    ///   have the same name or the range of a float is empty.
+    pub fn load_with_options(
+        reader: &mut (impl io::Read + io::Seek),
+        options: &LoadOptions,
+    ) -> Result<Self, Error> {
+        let wgs = Self::read_any(reader, options)?;
+
+        std::str::from_utf8(&wgs.frag)?;
+
+        wgs.check_limits(options)?;
+
+        Ok(wgs)
+    }
+
+    /// Reads wgs data in any of the layouts.
+    fn read_any(
+        reader: &mut (impl io::Read + io::Seek),
+        options: &LoadOptions,
+    ) -> Result<Self, Error> {
+        let position = reader.stream_position()?;
+
+        match Container::read(reader) {
+            Ok(container) => return Self::from_container(container, options),
+            Err(binrw::Error::BadMagic { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        reader.seek(io::SeekFrom::Start(position))?;
+
+        let result = WgsDataV1::read(reader)
+            .map_err(Error::from)
+            .and_then(Self::try_from);
+
+        if let Ok(wgs) = &result {
+            if wgs.meta.version == 1 {
+                return result;
+            }
+        }
+
+        // Only a header of another version points at the legacy layout, which has no version,
+        // so that a truncated or corrupted wgs 1 file fails as such.
+        reader.seek(io::SeekFrom::Start(position))?;
+        if !Meta::read(reader).is_ok_and(|meta| meta.version != 1) {
+            return result;
+        }
+
+        reader.seek(io::SeekFrom::Start(position))?;
+
+        match load_legacy(reader) {
+            Ok((wgs, _)) => Ok(wgs),
+            Err(_) => result.and_then(|wgs| Err(Error::UnsupportedVersion(wgs.meta.version))),
+        }
+    }
+
+    /// Loads wgs data in the legacy layout, which has no `version` field
+    /// and uses `texture` instead of `image` as the built-in function,
+    /// and upgrades it to the current version.
+    ///
+    /// Returns the upgraded data and the changes made.
+    pub fn load_legacy(
+        reader: &mut (impl io::Read + io::Seek),
+    ) -> Result<(Self, Migration), Error> {
+        load_legacy(reader)
     }
 
     pub fn new(name: &str, frag: &str) -> Self {
@@ -30,21 +229,105 @@ impl WgsData {
         let frag = NullString(frag.as_bytes().to_vec());
         Self {
             meta,
+            metadata: Metadata::default(),
             frag,
             textures: vec![],
+            params: vec![],
+            passes: vec![],
+            library: Library::default(),
+            thumbnail: None,
+            unknown_chunks: vec![],
         }
     }
 
+    /// Adds a parameter.
+    pub fn add_param(&mut self, param: Param) {
+        self.params.push(param);
+    }
+
+    /// Adds a buffer pass.
+    pub fn add_pass(&mut self, pass: Pass) {
+        self.passes.push(pass);
+    }
+
     /// Adds a texture.
-    pub fn add_texture(&mut self, width: u32, height: u32, data: Vec<u8>) {
-        self.textures.push(Texture::new(width, height, data));
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::SizeMismatch`] if the data is not 8bit RGBA of the size.
+    /// - Will return [`Error::TooManyTextures`] if there are already 255 textures.
+    pub fn add_texture(&mut self, width: u32, height: u32, data: Vec<u8>) -> Result<(), Error> {
+        if self.textures.len() >= MAX_TEXTURE_COUNT {
+            return Err(Error::TooManyTextures {
+                max: MAX_TEXTURE_COUNT,
+            });
+        }
+
+        self.textures.push(Texture::new(width, height, data)?);
         self.meta.texture_count = self.textures.len() as u8;
+
+        Ok(())
     }
 
-    /// Changes the texture of the current index.
-    pub fn change_texture(&mut self, index: usize, width: u32, height: u32, data: Vec<u8>) {
-        self.textures[index] = Texture::new(width, height, data);
-        self.meta.texture_count = self.textures.len() as u8;
+    /// Returns the author of the wgs data.
+    pub fn author(&self) -> String {
+        self.metadata.author.clone()
+    }
+
+    /// Changes the parameter of the given index.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no parameter at the index.
+    pub fn change_param(&mut self, index: usize, param: Param) -> Result<(), Error> {
+        *get_mut(&mut self.params, index)? = param;
+
+        Ok(())
+    }
+
+    /// Changes the buffer pass of the given index.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no pass at the index.
+    pub fn change_pass(&mut self, index: usize, pass: Pass) -> Result<(), Error> {
+        *get_mut(&mut self.passes, index)? = pass;
+
+        Ok(())
+    }
+
+    /// Changes the texture of the current index. Its encoding, sampler and name are kept.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    /// - Will return [`Error::SizeMismatch`] if the data is not 8bit RGBA of the size.
+    pub fn change_texture(
+        &mut self,
+        index: usize,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    ) -> Result<(), Error> {
+        let texture = get_mut(&mut self.textures, index)?;
+        *texture = Texture {
+            encoding: texture.encoding,
+            sampler: texture.sampler,
+            name: texture.name.take(),
+            ..Texture::new(width, height, data)?
+        };
+
+        Ok(())
+    }
+
+    /// Returns when the wgs data was created, in seconds since the Unix epoch.
+    pub fn created(&self) -> Option<u64> {
+        self.metadata.created
+    }
+
+    /// Returns the description of the wgs data.
+    pub fn description(&self) -> String {
+        self.metadata.description.clone()
     }
 
     /// Returns the content of the editable part of the fragment shader.
@@ -52,20 +335,288 @@ impl WgsData {
         self.frag.to_string()
     }
 
+    /// Inserts a texture at the index, shifting the textures after it.
+    ///
+    /// The texture inputs of the buffer passes keep pointing at the same textures.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if the index is greater than the number of textures.
+    /// - Will return [`Error::TooManyTextures`] if there are already 255 textures.
+    /// - Will return [`Error::SizeMismatch`] if the data is not 8bit RGBA of the size.
+    /// - Will return [`Error::InvalidTexture`] if the name is not an identifier or is taken,
+    ///   or if a texture without a name would be declared as a taken name after the shift.
+    pub fn insert_texture(&mut self, index: usize, texture: Texture) -> Result<(), Error> {
+        if index > self.textures.len() {
+            return Err(Error::IndexOutOfRange {
+                index,
+                len: self.textures.len(),
+            });
+        }
+
+        if self.textures.len() >= MAX_TEXTURE_COUNT {
+            return Err(Error::TooManyTextures {
+                max: MAX_TEXTURE_COUNT,
+            });
+        }
+
+        texture.check_size()?;
+
+        let len = self.textures.len();
+        self.textures.insert(index, texture);
+
+        if let Err(err) = check_texture_names(&self.textures, &self.passes) {
+            self.textures.remove(index);
+            return Err(err);
+        }
+
+        self.meta.texture_count = self.textures.len() as u8;
+
+        // Only the inputs reading existing textures follow them.
+        self.remap_texture_inputs(|texture| {
+            Some(texture + (index..len).contains(&texture) as usize)
+        });
+
+        Ok(())
+    }
+
+    /// Returns the WGSL modules embedded in the wgs data, which the frags can `#include`.
+    pub fn library_ref(&self) -> &Library {
+        &self.library
+    }
+
+    /// Returns the license of the wgs data, preferably an SPDX license identifier.
+    pub fn license(&self) -> String {
+        self.metadata.license.clone()
+    }
+
+    /// Returns when the wgs data was last modified, in seconds since the Unix epoch.
+    pub fn modified(&self) -> Option<u64> {
+        self.metadata.modified
+    }
+
+    /// Moves the texture at `from` to `to`, shifting the textures in between.
+    ///
+    /// The texture inputs of the buffer passes keep pointing at the same textures.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at either index.
+    pub fn move_texture(&mut self, from: usize, to: usize) -> Result<(), Error> {
+        get_mut(&mut self.textures, from)?;
+        get_mut(&mut self.textures, to)?;
+
+        let texture = self.textures.remove(from);
+        self.textures.insert(to, texture);
+
+        self.remap_texture_inputs(|texture| {
+            Some(if texture == from {
+                to
+            } else if from < texture && texture <= to {
+                texture - 1
+            } else if to <= texture && texture < from {
+                texture + 1
+            } else {
+                texture
+            })
+        });
+
+        Ok(())
+    }
+
     /// Returns the name of the wgs data. Not filename.
     pub fn name(&self) -> String {
         self.meta.name.to_string()
     }
 
-    /// Removes a texture.
-    pub fn remove_texture(&mut self, index: usize) {
+    /// Returns the parameters exposed to the shaders.
+    pub fn params_ref(&self) -> &Vec<Param> {
+        &self.params
+    }
+
+    /// Returns the indices of the buffer passes in the order to execute them,
+    /// so that each pass comes after the passes it reads.
+    ///
+    /// # Errors
+    ///
+    /// - Will return an error if a pass name is not a valid identifier, is used twice,
+    ///   or is taken by a declaration of the shader or by a param.
+    /// - Will return an error if a pass reads an unknown pass or texture, or reads something twice.
+    /// - Will return an error if passes read each other in a cycle. A pass may read itself, though.
+    ///
+    /// All of them are [`Error::InvalidPasses`].
+    pub fn pass_order(&self) -> Result<Vec<usize>, Error> {
+        sort_passes(&self.passes, &self.params, self.textures.len()).map_err(Error::InvalidPasses)
+    }
+
+    /// Returns the buffer passes rendered before the image.
+    pub fn passes_ref(&self) -> &Vec<Pass> {
+        &self.passes
+    }
+
+    /// Removes a parameter.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no parameter at the index.
+    pub fn remove_param(&mut self, index: usize) -> Result<(), Error> {
+        get_mut(&mut self.params, index)?;
+        self.params.remove(index);
+
+        Ok(())
+    }
+
+    /// Removes a buffer pass.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no pass at the index.
+    pub fn remove_pass(&mut self, index: usize) -> Result<(), Error> {
+        get_mut(&mut self.passes, index)?;
+        self.passes.remove(index);
+
+        Ok(())
+    }
+
+    /// Removes a texture. The passes stop reading it, and keep reading the textures after it.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    pub fn remove_texture(&mut self, index: usize) -> Result<(), Error> {
+        get_mut(&mut self.textures, index)?;
         self.textures.remove(index);
         self.meta.texture_count = self.textures.len() as u8;
+
+        self.remap_texture_inputs(|texture| match texture.cmp(&index) {
+            std::cmp::Ordering::Less => Some(texture),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(texture - 1),
+        });
+
+        Ok(())
+    }
+
+    /// Save wgs data to the writer in the layout of the latest version.
+    pub fn save(&self, writer: &mut (impl io::Write + io::Seek)) -> Result<(), Error> {
+        Ok(self.to_container()?.write(writer)?)
+    }
+
+    /// Generates the complete fragment shader of the image, along with a [`SourceMap`]
+    /// which maps the ranges of the shader back to [`Self::frag`], the included modules
+    /// or the built-in code.
+    ///
+    /// Each texture is bound to a group of its own, starting from group 1, and declared
+    /// after its name, see [`Texture::name`].
+    /// The outputs of the buffer passes are bound to the group following the textures, in order.
+    ///
+    /// The frag is preprocessed first: the `#include` directives are resolved against
+    /// [`Self::library_ref`], and the `#define` and `#ifdef` directives are applied with
+    /// `WGS_WEB` or `WGS_NATIVE`, `WGS_VERSION` and `WGS_TEXTURE_COUNT` defined.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::InvalidDirective`] if a directive is malformed or unbalanced.
+    /// - Will return [`Error::InvalidInclude`] if an include is unknown, if the includes form
+    ///   a cycle, or if a name is defined by more than one of the included modules and the frag.
+    /// - Will return [`Error::InvalidTexture`] if the name of a texture is not an identifier
+    ///   or is taken.
+    pub fn shader_frag(&self) -> Result<(String, SourceMap), Error> {
+        self.shader_frag_with_library(&Library::default())
+    }
+
+    /// Generates the complete fragment shader of the image like [`Self::shader_frag`],
+    /// resolving the includes the embedded library doesn't have against the library of the host.
+    pub fn shader_frag_with_library(
+        &self,
+        library: &Library,
+    ) -> Result<(String, SourceMap), Error> {
+        check_texture_names(&self.textures, &self.passes)?;
+
+        let mut declarations = declare_params(&self.params);
+        declarations.push_str(&declare_textures(self.texture_bindings()));
+        declarations.push_str(&declare_inputs(
+            self.textures.len() + 1,
+            self.passes
+                .iter()
+                .map(|pass| (pass.name.clone(), format!("{}_sampler", pass.name))),
+        ));
+
+        let frag = self.frag();
+        let pieces = preprocess(
+            &frag,
+            SourceOrigin::Frag,
+            &[&self.library, library],
+            builtin_defines(self.textures.len()),
+        )?;
+
+        Ok(assemble_shader(&declarations, &pieces, FRAG_SUFFIX))
+    }
+
+    /// Generates the complete fragment shader of the buffer pass of the given index,
+    /// along with a [`SourceMap`] which maps the ranges of the shader back to [`Pass::frag`],
+    /// the included modules or the built-in code.
+    ///
+    /// The inputs of the pass are bound to group 1, in the order of [`Pass::inputs`].
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no pass at the index.
+    /// - Will return [`Error::InvalidDirective`] or [`Error::InvalidInclude`] if the frag
+    ///   can't be preprocessed, see [`Self::shader_frag`].
+    pub fn shader_pass(&self, index: usize) -> Result<(String, SourceMap), Error> {
+        self.shader_pass_with_library(index, &Library::default())
     }
 
-    /// Save wgs data to the writer. [Read more](binrw::BinWrite::write).
-    pub fn save(&self, writer: &mut (impl io::Write + io::Seek)) -> BinResult<()> {
-        self.write(writer)
+    /// Generates the complete fragment shader of the buffer pass like [`Self::shader_pass`],
+    /// resolving the includes the embedded library doesn't have against the library of the host.
+    pub fn shader_pass_with_library(
+        &self,
+        index: usize,
+        library: &Library,
+    ) -> Result<(String, SourceMap), Error> {
+        let pass = self.passes.get(index).ok_or(Error::IndexOutOfRange {
+            index,
+            len: self.passes.len(),
+        })?;
+
+        check_texture_names(&self.textures, &self.passes)?;
+
+        let mut declarations = declare_params(&self.params);
+        declarations.push_str(&declare_inputs(
+            1,
+            pass.inputs.iter().map(|input| match input {
+                PassInput::Pass(name) => (name.clone(), format!("{}_sampler", name)),
+                PassInput::Texture(index) => match self.textures.get(*index) {
+                    Some(texture) => texture.bindings(*index),
+                    None => (format!("texture{}", index), format!("sampler{}", index)),
+                },
+            }),
+        ));
+
+        let pieces = preprocess(
+            &pass.frag,
+            SourceOrigin::Pass(pass.name.clone()),
+            &[&self.library, library],
+            builtin_defines(self.textures.len()),
+        )?;
+
+        Ok(assemble_shader(&declarations, &pieces, FRAG_PASS_SUFFIX))
+    }
+
+    /// Sets the author of the wgs data.
+    pub fn set_author(&mut self, author: &str) {
+        self.metadata.author = author.to_owned();
+    }
+
+    /// Sets when the wgs data was created, in seconds since the Unix epoch.
+    pub fn set_created(&mut self, created: Option<u64>) {
+        self.metadata.created = created;
+    }
+
+    /// Sets the description of the wgs data.
+    pub fn set_description(&mut self, description: &str) {
+        self.metadata.description = description.to_owned();
     }
 
     /// Sets the content of the editable part of the fragment shader.
@@ -73,18 +624,1221 @@ impl WgsData {
         self.frag.0 = frag.as_bytes().to_vec();
     }
 
+    /// Sets the WGSL modules embedded in the wgs data, which the frags can `#include`.
+    pub fn set_library(&mut self, library: Library) {
+        self.library = library;
+    }
+
+    /// Sets the license of the wgs data, preferably an SPDX license identifier.
+    pub fn set_license(&mut self, license: &str) {
+        self.metadata.license = license.to_owned();
+    }
+
+    /// Sets when the wgs data was last modified, in seconds since the Unix epoch.
+    pub fn set_modified(&mut self, modified: Option<u64>) {
+        self.metadata.modified = modified;
+    }
+
+    /// Sets the URL of the source the wgs data comes from.
+    pub fn set_source_url(&mut self, source_url: &str) {
+        self.metadata.source_url = source_url.to_owned();
+    }
+
+    /// Sets the free-form tags of the wgs data.
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.metadata.tags = tags;
+    }
+
+    /// Replaces the texture of the given index.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    /// - Will return [`Error::SizeMismatch`] if the data is not 8bit RGBA of the size.
+    /// - Will return [`Error::InvalidTexture`] if the name is not an identifier or is taken.
+    pub fn set_texture(&mut self, index: usize, texture: Texture) -> Result<(), Error> {
+        texture.check_size()?;
+
+        let previous = std::mem::replace(get_mut(&mut self.textures, index)?, texture);
+
+        if let Err(err) = check_texture_names(&self.textures, &self.passes) {
+            self.textures[index] = previous;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Sets how the texture of the given index is encoded when saved.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    pub fn set_texture_encoding(
+        &mut self,
+        index: usize,
+        encoding: TextureEncoding,
+    ) -> Result<(), Error> {
+        get_mut(&mut self.textures, index)?.encoding = encoding;
+
+        Ok(())
+    }
+
+    /// Sets the name the texture of the given index is declared as, see [`Texture::name`].
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    /// - Will return [`Error::InvalidTexture`] if the name is not an identifier or is taken.
+    pub fn set_texture_name(&mut self, index: usize, name: Option<&str>) -> Result<(), Error> {
+        let previous = std::mem::replace(
+            &mut get_mut(&mut self.textures, index)?.name,
+            name.map(str::to_owned),
+        );
+
+        if let Err(err) = check_texture_names(&self.textures, &self.passes) {
+            self.textures[index].name = previous;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Sets how the texture of the given index is sampled by the shader.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    pub fn set_texture_sampler(
+        &mut self,
+        index: usize,
+        sampler: TextureSampler,
+    ) -> Result<(), Error> {
+        get_mut(&mut self.textures, index)?.sampler = sampler;
+
+        Ok(())
+    }
+
     /// Sets the name for the wgs data.
     pub fn set_name(&mut self, name: &str) {
         self.meta.name.0 = name.as_bytes().to_vec();
     }
 
+    /// Sets the preview image of the wgs data, which can be shown without running the shader.
+    /// Its sampler is ignored.
+    pub fn set_thumbnail(&mut self, thumbnail: Option<Texture>) {
+        self.thumbnail = thumbnail;
+    }
+
+    /// Returns the URL of the source the wgs data comes from.
+    pub fn source_url(&self) -> String {
+        self.metadata.source_url.clone()
+    }
+
+    /// Swaps the textures at the indices.
+    ///
+    /// The texture inputs of the buffer passes keep pointing at the same textures.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at either index.
+    pub fn swap_textures(&mut self, a: usize, b: usize) -> Result<(), Error> {
+        get_mut(&mut self.textures, a)?;
+        get_mut(&mut self.textures, b)?;
+
+        self.textures.swap(a, b);
+
+        self.remap_texture_inputs(|texture| {
+            Some(if texture == a {
+                b
+            } else if texture == b {
+                a
+            } else {
+                texture
+            })
+        });
+
+        Ok(())
+    }
+
+    /// Returns the free-form tags of the wgs data.
+    pub fn tags_ref(&self) -> &Vec<String> {
+        &self.metadata.tags
+    }
+
+    /// Returns the texture at the index.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::IndexOutOfRange`] if there is no texture at the index.
+    pub fn texture(&self, index: usize) -> Result<&Texture, Error> {
+        self.textures.get(index).ok_or(Error::IndexOutOfRange {
+            index,
+            len: self.textures.len(),
+        })
+    }
+
+    /// Iterates over the textures embedded in the wgs data, in order.
+    pub fn textures(&self) -> std::slice::Iter<'_, Texture> {
+        self.textures.iter()
+    }
+
     /// Returns the textures embedded in the wgs data.
     pub fn textures_ref(&self) -> &Vec<Texture> {
         &self.textures
     }
 
+    /// Returns the preview image of the wgs data, if any.
+    pub fn thumbnail_ref(&self) -> Option<&Texture> {
+        self.thumbnail.as_ref()
+    }
+
+    /// Writes the wgs data to a project directory, which can be edited with common tools
+    /// and loaded back with [`Self::from_dir`] without losing anything.
+    ///
+    /// The directory holds `manifest.toml`, `frag.wgsl`, the frags of the buffer passes in
+    /// `passes/`, the modules of the library in `includes/`, the textures as PNG images in
+    /// `textures/` and `thumbnail.png`.
+    /// Existing files are overwritten.
+    ///
+    /// Requires the cargo feature `dir`.
+    #[cfg(feature = "dir")]
+    pub fn to_dir(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let dir = path.as_ref();
+
+        dir::write(dir, dir::FRAG, &self.frag)?;
+
+        let textures = self
+            .textures
+            .iter()
+            .enumerate()
+            .map(|(index, texture)| {
+                dir::TextureFile::write(dir, format!("textures/{}.png", index), texture)
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let thumbnail = self
+            .thumbnail
+            .as_ref()
+            .map(|thumbnail| dir::TextureFile::write(dir, dir::THUMBNAIL.to_owned(), thumbnail))
+            .transpose()?;
+
+        let mut taken = HashSet::new();
+        let passes = self
+            .passes
+            .iter()
+            .enumerate()
+            .map(|(index, pass)| {
+                let path = dir::wgsl_path("passes", &pass.name, index, &mut taken);
+                dir::write(dir, &path, pass.frag.as_bytes())?;
+
+                Ok(dir::PassFile {
+                    name: pass.name.clone(),
+                    path,
+                    inputs: pass.inputs.clone(),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let mut taken = HashSet::new();
+        let includes = self
+            .library
+            .iter()
+            .enumerate()
+            .map(|(index, (name, source))| {
+                let path = dir::wgsl_path("includes", name, index, &mut taken);
+                dir::write(dir, &path, source.as_bytes())?;
+
+                Ok(dir::IncludeFile {
+                    name: name.to_owned(),
+                    path,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        dir::write_manifest(
+            dir,
+            &dir::Manifest {
+                name: self.name(),
+                metadata: self.metadata.clone(),
+                textures,
+                thumbnail,
+                params: self.params.clone(),
+                passes,
+                includes,
+                unknown_chunks: self.unknown_chunks.clone(),
+            },
+        )
+    }
+
+    /// Returns the indices of the textures which neither the image nor any buffer pass reads.
+    ///
+    /// Returns the diagnostics if a shader fails to compile, see [`Self::validate`].
+    #[cfg(feature = "validate")]
+    pub fn unused_textures(&self) -> Result<Vec<usize>, Vec<Diagnostic>> {
+        self.unused_textures_with_library(&Library::default())
+    }
+
+    /// Returns the indices of the unused textures like [`Self::unused_textures`], resolving
+    /// the includes the embedded library doesn't have against the library of the host.
+    #[cfg(feature = "validate")]
+    pub fn unused_textures_with_library(
+        &self,
+        library: &Library,
+    ) -> Result<Vec<usize>, Vec<Diagnostic>> {
+        let shaders = (0..self.passes.len())
+            .map(|index| self.shader_pass_with_library(index, library))
+            .chain([self.shader_frag_with_library(library)]);
+
+        let mut read = HashSet::new();
+
+        for shader in shaders {
+            let (shader_frag, source_map) =
+                shader.map_err(|err| vec![Diagnostic::new(&err.to_string())])?;
+
+            let reflection = crate::reflect_shader_with_source_map(&shader_frag, &source_map)?;
+
+            read.extend(reflection.textures);
+        }
+
+        Ok(self
+            .texture_bindings()
+            .enumerate()
+            .filter(|(_, (name, _))| !read.contains(name))
+            .map(|(index, _)| index)
+            .collect())
+    }
+
+    /// Compiles the complete fragment shaders of the image and the buffer passes without a GPU
+    /// and returns the diagnostics on failure.
+    ///
+    /// The spans of the diagnostics refer to [`Self::frag`], [`Pass::frag`] or the included
+    /// modules, or to the generated shader if they point into built-in code.
+    #[cfg(feature = "validate")]
+    pub fn validate(&self) -> Result<(), Vec<Diagnostic>> {
+        self.validate_with_library(&Library::default())
+    }
+
+    /// Compiles the complete fragment shaders like [`Self::validate`], resolving the includes
+    /// the embedded library doesn't have against the library of the host.
+    #[cfg(feature = "validate")]
+    pub fn validate_with_library(&self, library: &Library) -> Result<(), Vec<Diagnostic>> {
+        let mut diagnostics = vec![];
+
+        if let Err(err) = self.pass_order() {
+            diagnostics.push(Diagnostic::new(&err.to_string()));
+        }
+
+        if let Err(err) = check_params(&self.params) {
+            diagnostics.push(Diagnostic::new(&err.to_string()));
+        }
+
+        let shaders = (0..self.passes.len())
+            .map(|index| self.shader_pass_with_library(index, library))
+            .chain([self.shader_frag_with_library(library)]);
+
+        for shader in shaders {
+            match shader {
+                Ok((shader_frag, source_map)) => {
+                    if let Err(err) =
+                        crate::validate_shader_with_source_map(&shader_frag, &source_map)
+                    {
+                        diagnostics.extend(err);
+                    }
+                }
+                Err(err) => diagnostics.push(Diagnostic::new(&err.to_string())),
+            }
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+
     /// Returns the version of the wgs data.
     pub fn version(&self) -> u32 {
         self.meta.version
     }
+
+    /// Checks the loaded data against the limits of the options.
+    fn check_limits(&self, options: &LoadOptions) -> Result<(), LimitError> {
+        let mut budget = Budget::new(options);
+
+        budget.check_name(&self.meta.name)?;
+        budget.check_frag(&self.frag)?;
+
+        for texture in self.textures.iter().chain(&self.thumbnail) {
+            budget.take_texture(texture.width, texture.height)?;
+
+            if let Some(name) = &texture.name {
+                budget.check_name(name.as_bytes())?;
+            }
+        }
+
+        for param in &self.params {
+            budget.check_name(param.name.as_bytes())?;
+        }
+
+        for pass in &self.passes {
+            budget.check_name(pass.name.as_bytes())?;
+            budget.check_frag(pass.frag.as_bytes())?;
+        }
+
+        for (name, source) in self.library.iter() {
+            budget.check_name(name.as_bytes())?;
+            budget.check_frag(source.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn from_container(container: Container, options: &LoadOptions) -> Result<Self, Error> {
+        let mut wgs = Self::new("", "");
+        wgs.meta.version = container.version;
+
+        // Verified before anything else, so that corrupted chunks are reported as such.
+        if options.verify_checksum {
+            for (index, chunk) in container.chunks.iter().enumerate() {
+                if chunk.tag == chunk::CHECKSUM {
+                    let expected: u32 = chunk.payload()?;
+                    let found = checksum(container.version, &container.chunks[..index]);
+
+                    if expected != found {
+                        return Err(ChecksumError { expected, found }.into());
+                    }
+                }
+            }
+        }
+
+        let mut budget = Budget::new(options);
+
+        for chunk in &container.chunks {
+            match chunk.tag {
+                chunk::META => {
+                    let meta: MetaChunk = chunk.payload()?;
+                    wgs.meta.name = meta.name;
+                }
+                chunk::INFO => {
+                    let info: InfoChunk = chunk.payload()?;
+
+                    wgs.metadata = Metadata {
+                        author: info.author.to_string(),
+                        description: info.description.to_string(),
+                        license: info.license.to_string(),
+                        source_url: info.source_url.to_string(),
+                        tags: info.tags.iter().map(|tag| tag.to_string()).collect(),
+                        created: info.timestamp(info.created, InfoChunk::CREATED),
+                        modified: info.timestamp(info.modified, InfoChunk::MODIFIED),
+                    };
+                }
+                chunk::FRAG => {
+                    wgs.frag = NullString(chunk.data.clone());
+                }
+                chunk::TEXTURE => {
+                    if wgs.textures.len() >= MAX_TEXTURE_COUNT {
+                        return Err(Error::TooManyTextures {
+                            max: MAX_TEXTURE_COUNT,
+                        });
+                    }
+
+                    wgs.textures.push(read_texture(chunk, &mut budget)?);
+                }
+                chunk::THUMBNAIL => {
+                    wgs.thumbnail = Some(read_texture(chunk, &mut budget)?);
+                }
+                chunk::PARAM => {
+                    let param: ParamChunk = chunk.payload()?;
+
+                    let kind = match param.kind {
+                        ParamKindChunk::Bool { default } => ParamKind::Bool {
+                            default: default != 0,
+                        },
+                        ParamKindChunk::Color { default } => ParamKind::Color { default },
+                        ParamKindChunk::Enum { default, options } => ParamKind::Enum {
+                            default,
+                            options: options.iter().map(|option| option.to_string()).collect(),
+                        },
+                        ParamKindChunk::Float { default, min, max } => {
+                            ParamKind::Float { default, min, max }
+                        }
+                    };
+
+                    wgs.params.push(Param::new(&param.name.to_string(), kind));
+                }
+                chunk::PASS => {
+                    let pass: PassChunk = chunk.payload()?;
+
+                    wgs.passes.push(Pass {
+                        name: pass.name.to_string(),
+                        frag: String::from_utf8(pass.frag).map_err(|err| err.utf8_error())?,
+                        inputs: pass
+                            .inputs
+                            .into_iter()
+                            .map(|input| match input {
+                                PassInputChunk::Pass(name) => PassInput::Pass(name.to_string()),
+                                PassInputChunk::Texture(index) => {
+                                    PassInput::Texture(index as usize)
+                                }
+                            })
+                            .collect(),
+                    });
+                }
+                chunk::INCLUDE => {
+                    let include: IncludeChunk = chunk.payload()?;
+
+                    wgs.library.insert(
+                        &include.name.to_string(),
+                        std::str::from_utf8(&include.source)?,
+                    );
+                }
+                chunk::CHECKSUM | chunk::END => {}
+                _ => wgs.unknown_chunks.push(chunk.clone()),
+            }
+        }
+
+        check_params(&wgs.params)?;
+
+        wgs.meta.texture_count = wgs.textures.len() as u8;
+
+        Ok(wgs)
+    }
+
+    /// Points the texture inputs of the buffer passes at the textures after a change of order.
+    /// Inputs mapped to `None` are removed.
+    fn remap_texture_inputs(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        for pass in &mut self.passes {
+            pass.inputs = std::mem::take(&mut pass.inputs)
+                .into_iter()
+                .filter_map(|input| match input {
+                    PassInput::Texture(texture) => remap(texture).map(PassInput::Texture),
+                    input => Some(input),
+                })
+                .collect();
+        }
+    }
+
+    /// Returns the names each texture and its sampler are declared as, in order.
+    fn texture_bindings(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.textures
+            .iter()
+            .enumerate()
+            .map(|(index, texture)| texture.bindings(index))
+    }
+
+    fn to_container(&self) -> Result<Container, Error> {
+        let mut chunks = vec![Chunk::with_payload(
+            chunk::META,
+            &MetaChunk {
+                name: self.meta.name.clone(),
+            },
+        )?];
+
+        if !self.metadata.is_empty() {
+            chunks.push(Chunk::with_payload(
+                chunk::INFO,
+                &InfoChunk {
+                    author: self.metadata.author.as_str().into(),
+                    description: self.metadata.description.as_str().into(),
+                    license: self.metadata.license.as_str().into(),
+                    source_url: self.metadata.source_url.as_str().into(),
+                    tags: self
+                        .metadata
+                        .tags
+                        .iter()
+                        .map(|tag| tag.as_str().into())
+                        .collect(),
+                    created: self.metadata.created.unwrap_or_default(),
+                    modified: self.metadata.modified.unwrap_or_default(),
+                    timestamps: Some(
+                        self.metadata.created.map_or(0, |_| InfoChunk::CREATED)
+                            | self.metadata.modified.map_or(0, |_| InfoChunk::MODIFIED),
+                    ),
+                },
+            )?);
+        }
+
+        chunks.push(Chunk::new(chunk::FRAG, self.frag.0.clone()));
+
+        if let Some(thumbnail) = &self.thumbnail {
+            chunks.push(write_texture(chunk::THUMBNAIL, thumbnail, false)?);
+        }
+
+        for texture in &self.textures {
+            chunks.push(write_texture(chunk::TEXTURE, texture, true)?);
+        }
+
+        for param in &self.params {
+            let kind = match &param.kind {
+                ParamKind::Bool { default } => ParamKindChunk::Bool {
+                    default: *default as u8,
+                },
+                ParamKind::Color { default } => ParamKindChunk::Color { default: *default },
+                ParamKind::Enum { default, options } => ParamKindChunk::Enum {
+                    default: *default,
+                    options: options
+                        .iter()
+                        .map(|option| option.as_str().into())
+                        .collect(),
+                },
+                ParamKind::Float { default, min, max } => ParamKindChunk::Float {
+                    default: *default,
+                    min: *min,
+                    max: *max,
+                },
+            };
+
+            chunks.push(Chunk::with_payload(
+                chunk::PARAM,
+                &ParamChunk {
+                    name: param.name.as_str().into(),
+                    kind,
+                },
+            )?);
+        }
+
+        for pass in &self.passes {
+            let inputs = pass
+                .inputs
+                .iter()
+                .map(|input| {
+                    Ok(match input {
+                        PassInput::Pass(name) => PassInputChunk::Pass(name.as_str().into()),
+                        PassInput::Texture(index) => {
+                            PassInputChunk::Texture(u32::try_from(*index).map_err(|_| {
+                                Error::IndexOutOfRange {
+                                    index: *index,
+                                    len: self.textures.len(),
+                                }
+                            })?)
+                        }
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+
+            chunks.push(Chunk::with_payload(
+                chunk::PASS,
+                &PassChunk {
+                    name: pass.name.as_str().into(),
+                    frag: pass.frag.as_bytes().to_vec(),
+                    inputs,
+                },
+            )?);
+        }
+
+        for (name, source) in self.library.iter() {
+            chunks.push(Chunk::with_payload(
+                chunk::INCLUDE,
+                &IncludeChunk {
+                    name: name.into(),
+                    source: source.as_bytes().to_vec(),
+                },
+            )?);
+        }
+
+        chunks.extend(self.unknown_chunks.iter().cloned());
+        chunks.push(Chunk::new(
+            chunk::CHECKSUM,
+            checksum(VERSION, &chunks).to_le_bytes().to_vec(),
+        ));
+        chunks.push(Chunk::new(chunk::END, vec![]));
+
+        Ok(Container {
+            version: VERSION,
+            chunks,
+        })
+    }
+}
+
+impl TryFrom<WgsDataV1> for WgsData {
+    type Error = Error;
+
+    fn try_from(data: WgsDataV1) -> Result<Self, Error> {
+        Ok(Self {
+            meta: data.meta,
+            metadata: Metadata::default(),
+            frag: data.frag,
+            textures: data.textures,
+            params: vec![],
+            passes: vec![],
+            library: Library::default(),
+            thumbnail: None,
+            unknown_chunks: vec![],
+        })
+    }
+}
+
+/// Returns the item at the index, or [`Error::IndexOutOfRange`].
+fn get_mut<T>(items: &mut [T], index: usize) -> Result<&mut T, Error> {
+    let len = items.len();
+
+    items
+        .get_mut(index)
+        .ok_or(Error::IndexOutOfRange { index, len })
+}
+
+/// Reads the payload of a [`chunk::TEXTURE`] or a [`chunk::THUMBNAIL`] chunk.
+///
+/// The size of the texture is taken out of the budget before decoding.
+fn read_texture(chunk: &Chunk, budget: &mut Budget) -> Result<Texture, Error> {
+    let texture: TextureChunk = chunk.payload()?;
+
+    budget.take_texture(texture.width, texture.height)?;
+
+    let encoding = texture.encoding.unwrap_or_default();
+    let encoding = TextureEncoding::from_u8(encoding)
+        .ok_or_else(|| Error::Codec(format!("Unknown texture encoding {}", encoding)))?;
+
+    let data = codec::decode(encoding, texture.width, texture.height, texture.data)
+        .map_err(Error::Codec)?;
+
+    let sampler = texture.sampler.map(TextureSampler::from_bytes);
+
+    if let Some(name) = &texture.name {
+        budget.check_name(name)?;
+    }
+
+    let name = texture.name.map(|name| name.to_string());
+
+    let mut texture = Texture::new(texture.width, texture.height, data)?;
+    texture.encoding = encoding;
+    texture.sampler = sampler.unwrap_or_default();
+    texture.name = name;
+
+    Ok(texture)
+}
+
+/// Creates a [`chunk::TEXTURE`] or a [`chunk::THUMBNAIL`] chunk holding the texture.
+fn write_texture(tag: [u8; 4], texture: &Texture, with_sampler: bool) -> Result<Chunk, Error> {
+    let data = codec::encode(
+        texture.encoding,
+        texture.width,
+        texture.height,
+        &texture.data,
+    )
+    .map_err(Error::Codec)?;
+
+    Ok(Chunk::with_payload(
+        tag,
+        &TextureChunk {
+            width: texture.width,
+            height: texture.height,
+            data,
+            encoding: Some(texture.encoding.to_u8()),
+            sampler: with_sampler.then(|| texture.sampler.to_bytes()),
+            name: texture
+                .name
+                .as_deref()
+                .filter(|_| with_sampler)
+                .map(NullString::from),
+        },
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codec::tests::encodings, AddressMode};
+
+    /// Wgs data using every chunk, with a texture in each encoding.
+    fn sample() -> WgsData {
+        let mut wgs = WgsData::new("sample", "fn main_image() {}");
+        wgs.set_author("author");
+        wgs.set_tags(vec!["tag".to_owned()]);
+        wgs.set_created(Some(1));
+
+        for (index, encoding) in encodings().into_iter().enumerate() {
+            let data = (0..2 * 2 * 4).map(|byte| byte * 15).collect();
+            wgs.add_texture(2, 2, data).unwrap();
+            wgs.set_texture_encoding(index, encoding).unwrap();
+        }
+        wgs.set_texture_name(0, Some("noise")).unwrap();
+        wgs.set_texture_sampler(
+            0,
+            TextureSampler {
+                address_mode: AddressMode::Repeat,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        wgs.set_thumbnail(Some(Texture::new(1, 1, vec![1, 2, 3, 4]).unwrap()));
+
+        wgs.add_param(Param::new("on", ParamKind::Bool { default: true }));
+        let mut pass = Pass::new("buf", "fn main_image() {}");
+        pass.inputs = vec![PassInput::Texture(0), PassInput::Pass("buf".to_owned())];
+        wgs.add_pass(pass);
+
+        let mut library = Library::new();
+        library.insert("module", "fn f() {}");
+        wgs.set_library(library);
+
+        wgs
+    }
+
+    fn save(wgs: &WgsData) -> Vec<u8> {
+        let mut file = io::Cursor::new(vec![]);
+        wgs.save(&mut file).unwrap();
+
+        file.into_inner()
+    }
+
+    /// Forges a texture chunk of the size holding the data in the encoding as is.
+    fn texture_chunk(tag: [u8; 4], width: u32, height: u32, encoding: TextureEncoding) -> Chunk {
+        Chunk::with_payload(
+            tag,
+            &TextureChunk {
+                width,
+                height,
+                data: vec![0; 16],
+                encoding: Some(encoding.to_u8()),
+                sampler: None,
+                name: None,
+            },
+        )
+        .unwrap()
+    }
+
+    /// Writes the chunks as a wgs file, followed by their checksum.
+    fn write_chunks(mut chunks: Vec<Chunk>) -> io::Cursor<Vec<u8>> {
+        chunks.retain(|chunk| chunk.tag != chunk::CHECKSUM && chunk.tag != chunk::END);
+        chunks.push(Chunk::new(
+            chunk::CHECKSUM,
+            checksum(VERSION, &chunks).to_le_bytes().to_vec(),
+        ));
+        chunks.push(Chunk::new(chunk::END, vec![]));
+
+        let mut cursor = io::Cursor::new(vec![]);
+        Container {
+            version: VERSION,
+            chunks,
+        }
+        .write(&mut cursor)
+        .unwrap();
+        cursor.set_position(0);
+
+        cursor
+    }
+
+    /// Lays out a wgs 1 file, or a legacy one without the version.
+    fn v1_file(name: &str, version: Option<u32>, frag: &str) -> Vec<u8> {
+        let mut file = [name.as_bytes(), &[0, 0]].concat();
+        if let Some(version) = version {
+            file.extend(version.to_le_bytes());
+        }
+        file.extend(frag.as_bytes());
+        file.push(0);
+
+        file
+    }
+
+    #[test]
+    fn budget_is_taken_before_decoding_in_each_encoding() {
+        let options = LoadOptions {
+            max_texture_dimension: 64,
+            max_total_bytes: 64,
+            ..Default::default()
+        };
+
+        for tag in [chunk::TEXTURE, chunk::THUMBNAIL] {
+            for encoding in encodings() {
+                let mut file = write_chunks(vec![texture_chunk(tag, 65, 1, encoding)]);
+                let result = WgsData::load_with_options(&mut file, &options);
+                assert!(
+                    matches!(
+                        result,
+                        Err(Error::Limit(LimitError::TextureDimension { .. }))
+                    ),
+                    "{:?}",
+                    result
+                );
+
+                let mut file = write_chunks(vec![texture_chunk(tag, 4, 5, encoding)]);
+                let result = WgsData::load_with_options(&mut file, &options);
+                assert!(
+                    matches!(result, Err(Error::Limit(LimitError::TotalBytes { .. }))),
+                    "{:?}",
+                    result
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn checksum_mismatch_fails_unless_ignored() {
+        let mut file = save(&WgsData::new("checked", "fn main_image() {}"));
+        let index = file.windows(2).position(|bytes| bytes == b"fn").unwrap();
+        file[index] = b'F';
+
+        let result = WgsData::load(&mut io::Cursor::new(&file));
+        assert!(matches!(result, Err(Error::Checksum(_))), "{:?}", result);
+
+        let options = LoadOptions {
+            verify_checksum: false,
+            ..Default::default()
+        };
+        let wgs = WgsData::load_with_options(&mut io::Cursor::new(&file), &options).unwrap();
+        assert_eq!(wgs.frag(), "Fn main_image() {}");
+    }
+
+    #[test]
+    fn data_round_trips() {
+        let wgs = sample();
+        let file = save(&wgs);
+        let loaded = WgsData::load(&mut io::Cursor::new(&file)).unwrap();
+
+        assert_eq!(loaded.name(), wgs.name());
+        assert_eq!(loaded.frag(), wgs.frag());
+        assert_eq!(loaded.metadata, wgs.metadata);
+        assert_eq!(loaded.params_ref(), wgs.params_ref());
+        assert_eq!(loaded.passes_ref(), wgs.passes_ref());
+        assert_eq!(loaded.library_ref(), wgs.library_ref());
+        assert_eq!(loaded.textures_ref().len(), wgs.textures_ref().len());
+        for (loaded, texture) in loaded.textures().zip(wgs.textures()) {
+            assert_eq!(loaded.data, texture.data);
+            assert_eq!(loaded.encoding, texture.encoding);
+            assert_eq!(loaded.sampler, texture.sampler);
+            assert_eq!(loaded.name, texture.name);
+        }
+        assert_eq!(
+            loaded.thumbnail_ref().map(|thumbnail| &thumbnail.data),
+            wgs.thumbnail_ref().map(|thumbnail| &thumbnail.data)
+        );
+
+        assert_eq!(save(&loaded), file);
+    }
+
+    #[test]
+    fn every_truncation_fails() {
+        let file = save(&sample());
+
+        for len in 0..file.len() {
+            let result = WgsData::load(&mut io::Cursor::new(&file[..len]));
+            assert!(
+                matches!(result, Err(Error::Truncated)),
+                "{}: {:?}",
+                len,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_chunks_are_preserved() {
+        let mut chunks = sample().to_container().unwrap().chunks;
+        chunks.insert(1, Chunk::new(*b"XTRA", vec![1, 2, 3]));
+        let file = write_chunks(chunks).into_inner();
+
+        let wgs = WgsData::load(&mut io::Cursor::new(&file)).unwrap();
+        assert_eq!(wgs.unknown_chunks.len(), 1);
+        assert_eq!(wgs.unknown_chunks[0].tag, *b"XTRA");
+        assert_eq!(wgs.unknown_chunks[0].data, [1, 2, 3]);
+
+        let resaved = save(&wgs);
+        assert!(resaved.windows(8).any(|bytes| bytes == b"XTRA\x03\0\0\0"));
+
+        let reloaded = WgsData::load(&mut io::Cursor::new(resaved)).unwrap();
+        assert_eq!(reloaded.unknown_chunks.len(), 1);
+        assert_eq!(reloaded.unknown_chunks[0].tag, *b"XTRA");
+        assert_eq!(reloaded.unknown_chunks[0].data, [1, 2, 3]);
+    }
+
+    #[test]
+    fn invalid_param_fails_to_load() {
+        let mut wgs = WgsData::default();
+        wgs.add_param(Param::new(
+            "gain",
+            ParamKind::Float {
+                default: 0.5,
+                min: 1.0,
+                max: 0.0,
+            },
+        ));
+
+        let mut file = io::Cursor::new(vec![]);
+        wgs.save(&mut file).unwrap();
+        file.set_position(0);
+
+        let result = WgsData::load(&mut file);
+        assert!(
+            matches!(result, Err(Error::InvalidParam(_))),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn legacy_file_is_migrated() {
+        let file = v1_file(
+            "legacy",
+            None,
+            "fn main_image() -> vec4<f32> { return texture(t, s, uv); }",
+        );
+
+        let wgs = WgsData::load(&mut io::Cursor::new(file)).unwrap();
+        assert_eq!(wgs.name(), "legacy");
+        assert_eq!(wgs.version(), VERSION);
+        assert_eq!(
+            wgs.frag(),
+            "fn main_image() -> vec4<f32> { return image(t, s, uv); }"
+        );
+    }
+
+    #[test]
+    fn truncated_v1_file_fails() {
+        let mut file = v1_file(
+            "v1",
+            Some(1),
+            "fn main_image() -> vec4<f32> { return vec4(1.0); }",
+        );
+        file.truncate(file.len() - 40);
+
+        let result = WgsData::load(&mut io::Cursor::new(file));
+        assert!(matches!(result, Err(Error::Truncated)), "{:?}", result);
+    }
+
+    #[test]
+    fn v1_file_is_loaded() {
+        let file = v1_file("v1", Some(1), "fn main_image() {}");
+
+        let wgs = WgsData::load(&mut io::Cursor::new(file)).unwrap();
+        assert_eq!(wgs.name(), "v1");
+        assert_eq!(wgs.frag(), "fn main_image() {}");
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_header_of_another_size_fails_to_load() {
+        let mut wgs = WgsData::default();
+        wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+        wgs.set_texture_encoding(0, TextureEncoding::Png).unwrap();
+
+        let mut chunks = wgs.to_container().unwrap().chunks;
+        let texture = chunks
+            .iter_mut()
+            .find(|chunk| chunk.tag == chunk::TEXTURE)
+            .unwrap();
+        let mut payload: TextureChunk = texture.payload().unwrap();
+        payload.data = crate::codec::tests::resize_png_header(payload.data, 20000, 20000);
+        *texture = Chunk::with_payload(chunk::TEXTURE, &payload).unwrap();
+
+        let result = WgsData::load(&mut write_chunks(chunks));
+        assert!(matches!(result, Err(Error::Codec(_))), "{:?}", result);
+    }
+
+    /// Wgs data of three 1x1 textures, with a pass reading each of them.
+    fn three_textures() -> WgsData {
+        let mut wgs = WgsData::new("textures", "fn main_image() {}");
+        for texture in 0..3 {
+            wgs.add_texture(1, 1, vec![texture; 4]).unwrap();
+        }
+
+        let mut pass = Pass::new("buf", "fn main_image() {}");
+        pass.inputs = (0..3).map(PassInput::Texture).collect();
+        wgs.add_pass(pass);
+
+        wgs
+    }
+
+    /// Returns the first byte of each texture, along with the textures the pass reads.
+    fn texture_order(wgs: &WgsData) -> (Vec<u8>, Vec<usize>) {
+        let textures = wgs.textures().map(|texture| texture.data[0]).collect();
+        let inputs = wgs.passes_ref()[0]
+            .inputs
+            .iter()
+            .map(|input| match input {
+                PassInput::Texture(index) => *index,
+                PassInput::Pass(_) => unreachable!(),
+            })
+            .collect();
+
+        (textures, inputs)
+    }
+
+    #[test]
+    fn inserted_texture_shifts_the_inputs() {
+        let mut wgs = three_textures();
+        wgs.insert_texture(1, Texture::new(1, 1, vec![9; 4]).unwrap())
+            .unwrap();
+
+        assert_eq!(texture_order(&wgs), (vec![0, 9, 1, 2], vec![0, 2, 3]));
+        assert_eq!(wgs.meta.texture_count, 4);
+
+        let result = wgs.insert_texture(5, Texture::new(1, 1, vec![9; 4]).unwrap());
+        assert!(matches!(result, Err(Error::IndexOutOfRange { .. })));
+    }
+
+    #[test]
+    fn inserted_texture_is_checked() {
+        let mut wgs = three_textures();
+
+        let mut texture = Texture::new(1, 1, vec![9; 4]).unwrap();
+        texture.width = 2;
+        let result = wgs.insert_texture(0, texture);
+        assert!(
+            matches!(result, Err(Error::SizeMismatch { .. })),
+            "{:?}",
+            result
+        );
+
+        for name in ["buf", "u", "texture2", "1st"] {
+            let mut texture = Texture::new(1, 1, vec![9; 4]).unwrap();
+            texture.name = Some(name.to_owned());
+            let result = wgs.insert_texture(0, texture);
+            assert!(matches!(result, Err(Error::InvalidTexture(_))), "{}", name);
+        }
+
+        assert_eq!(texture_order(&wgs), (vec![0, 1, 2], vec![0, 1, 2]));
+        assert_eq!(wgs.meta.texture_count, 3);
+    }
+
+    #[test]
+    fn moved_texture_keeps_the_inputs() {
+        let mut wgs = three_textures();
+        wgs.move_texture(0, 2).unwrap();
+        assert_eq!(texture_order(&wgs), (vec![1, 2, 0], vec![2, 0, 1]));
+
+        wgs.move_texture(2, 0).unwrap();
+        assert_eq!(texture_order(&wgs), (vec![0, 1, 2], vec![0, 1, 2]));
+
+        assert!(wgs.move_texture(0, 3).is_err());
+    }
+
+    #[test]
+    fn removed_texture_is_dropped_from_the_inputs() {
+        let mut wgs = three_textures();
+        wgs.remove_texture(1).unwrap();
+
+        assert_eq!(texture_order(&wgs), (vec![0, 2], vec![0, 1]));
+        assert_eq!(wgs.meta.texture_count, 2);
+    }
+
+    #[test]
+    fn set_texture_is_checked() {
+        let mut wgs = three_textures();
+
+        let mut texture = Texture::new(1, 1, vec![9; 4]).unwrap();
+        texture.data.push(0);
+        let result = wgs.set_texture(0, texture);
+        assert!(
+            matches!(result, Err(Error::SizeMismatch { .. })),
+            "{:?}",
+            result
+        );
+
+        let mut texture = Texture::new(1, 1, vec![9; 4]).unwrap();
+        texture.name = Some("sampler1".to_owned());
+        let result = wgs.set_texture(0, texture);
+        assert!(
+            matches!(result, Err(Error::InvalidTexture(_))),
+            "{:?}",
+            result
+        );
+        assert_eq!(wgs.texture(0).unwrap().data, [0; 4]);
+
+        let mut texture = Texture::new(1, 1, vec![9; 4]).unwrap();
+        texture.name = Some("noise".to_owned());
+        wgs.set_texture(0, texture).unwrap();
+        assert_eq!(wgs.texture(0).unwrap().name.as_deref(), Some("noise"));
+    }
+
+    #[test]
+    fn swapped_textures_keep_the_inputs() {
+        let mut wgs = three_textures();
+        wgs.swap_textures(0, 2).unwrap();
+
+        assert_eq!(texture_order(&wgs), (vec![2, 1, 0], vec![2, 1, 0]));
+        assert!(wgs.swap_textures(0, 3).is_err());
+    }
+
+    #[test]
+    fn timestamps_of_zero_round_trip() {
+        for (created, modified) in [(Some(0), None), (None, Some(0)), (Some(1), Some(2))] {
+            let mut wgs = WgsData::new("timestamps", "fn main_image() {}");
+            wgs.set_created(created);
+            wgs.set_modified(modified);
+
+            let wgs = WgsData::load(&mut io::Cursor::new(save(&wgs))).unwrap();
+            assert_eq!((wgs.created(), wgs.modified()), (created, modified));
+        }
+    }
+
+    #[test]
+    fn timestamps_of_zero_without_flags_are_unknown() {
+        let info = InfoChunk {
+            author: "".into(),
+            description: "".into(),
+            license: "".into(),
+            source_url: "".into(),
+            tags: vec![],
+            created: 0,
+            modified: 2,
+            timestamps: None,
+        };
+        let chunks = vec![
+            Chunk::with_payload(chunk::INFO, &info).unwrap(),
+            Chunk::new(chunk::FRAG, b"fn main_image() {}".to_vec()),
+        ];
+
+        let wgs = WgsData::load(&mut write_chunks(chunks)).unwrap();
+        assert_eq!((wgs.created(), wgs.modified()), (None, Some(2)));
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn valid_data_has_no_diagnostics() {
+        let mut wgs = WgsData::new("valid", crate::FRAG_DEFAULT);
+        wgs.add_pass(Pass::new("buf", crate::FRAG_DEFAULT));
+
+        assert!(wgs.validate().is_ok());
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn diagnostics_point_into_the_frags() {
+        let broken = "fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {\n    return q;\n}";
+        let mut wgs = WgsData::new("broken", broken);
+        wgs.add_pass(Pass::new("buf", broken));
+
+        let diagnostics = wgs.validate().unwrap_err();
+        let origins: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let label = &diagnostic.labels[0];
+                (label.origin.clone(), label.line, label.column)
+            })
+            .collect();
+
+        assert_eq!(
+            origins,
+            [
+                (SourceOrigin::Pass("buf".to_owned()), 2, 12),
+                (SourceOrigin::Frag, 2, 12),
+            ]
+        );
+        assert!(diagnostics[1]
+            .to_string()
+            .ends_with("at 2:12: unknown identifier"));
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn invalid_passes_and_params_are_diagnosed() {
+        let mut wgs = WgsData::new("invalid", crate::FRAG_DEFAULT);
+        let mut pass = Pass::new("buf", crate::FRAG_DEFAULT);
+        pass.inputs = vec![PassInput::Pass("other".to_owned())];
+        wgs.add_pass(pass);
+        wgs.add_param(Param::new("loop", ParamKind::Bool { default: true }));
+
+        let diagnostics = wgs.validate().unwrap_err();
+        let messages: Vec<_> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.labels.is_empty())
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+
+        assert_eq!(messages.len(), 2, "{:?}", diagnostics);
+        assert!(messages[0].contains("`other`"), "{}", messages[0]);
+        assert!(messages[1].contains("`loop`"), "{}", messages[1]);
+    }
 }
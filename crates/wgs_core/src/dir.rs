@@ -0,0 +1,217 @@
+//! The project directory of wgs data, with the cargo feature `dir`.
+//!
+//! ```text
+//! manifest.toml         the name, the metadata, the parameters and what refers to the files below
+//! frag.wgsl             the editable part of the fragment shader of the image
+//! passes/{name}.wgsl    the editable part of the fragment shader of each buffer pass
+//! includes/{name}.wgsl  each module of the library
+//! textures/{n}.png      each texture
+//! thumbnail.png         the preview image, if any
+//! ```
+
+use crate::{
+    chunk::Chunk,
+    codec,
+    meta::Metadata,
+    param::Param,
+    pass::{is_identifier, PassInput},
+    sampler::TextureSampler,
+    texture::{Texture, TextureEncoding},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, io, path::Path};
+
+/// The file name of the manifest.
+pub(crate) const MANIFEST: &str = "manifest.toml";
+/// The file name of the frag of the image.
+pub(crate) const FRAG: &str = "frag.wgsl";
+/// The file name of the thumbnail.
+pub(crate) const THUMBNAIL: &str = "thumbnail.png";
+
+/// The content of `manifest.toml`. Paths are relative to the directory.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Manifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub textures: Vec<TextureFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<TextureFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Param>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub passes: Vec<PassFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<IncludeFile>,
+    /// The chunks unknown to this version, with their data as base64.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_chunks: Vec<Chunk>,
+}
+
+/// A texture stored as a PNG image.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TextureFile {
+    pub path: String,
+    /// How the texture is encoded when packed. The image is always PNG in the directory.
+    #[serde(default)]
+    pub encoding: TextureEncoding,
+    #[serde(default)]
+    pub sampler: TextureSampler,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A buffer pass with its frag stored in a file.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PassFile {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub inputs: Vec<PassInput>,
+}
+
+/// A module of the library stored in a file.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IncludeFile {
+    pub name: String,
+    pub path: String,
+}
+
+impl TextureFile {
+    /// Reads the texture from its PNG image, which may be of any color type.
+    pub fn read(&self, dir: &Path) -> Result<Texture, Error> {
+        let (width, height, data) = codec::decode_png(&read(dir, &self.path)?, None)
+            .map_err(|err| Error::Codec(format!("{}: {}", self.path, err)))?;
+
+        let mut texture = Texture::new(width, height, data)?;
+        texture.encoding = self.encoding;
+        texture.sampler = self.sampler;
+        texture.name = self.name.clone();
+
+        Ok(texture)
+    }
+
+    /// Writes the texture as a PNG image to the path.
+    pub fn write(dir: &Path, path: String, texture: &Texture) -> Result<Self, Error> {
+        let png = codec::encode_png(texture.width, texture.height, &texture.data)
+            .map_err(Error::Codec)?;
+
+        write(dir, &path, &png)?;
+
+        Ok(Self {
+            path,
+            encoding: texture.encoding,
+            sampler: texture.sampler,
+            name: texture.name.clone(),
+        })
+    }
+}
+
+/// Returns the path of a WGSL file in the folder, named after the pass or the module if the name
+/// can be a file name which is not taken yet, or after its index otherwise.
+pub(crate) fn wgsl_path(
+    folder: &str,
+    name: &str,
+    index: usize,
+    taken: &mut HashSet<String>,
+) -> String {
+    if is_identifier(name) && taken.insert(name.to_owned()) {
+        format!("{}/{}.wgsl", folder, name)
+    } else {
+        format!("{}/{}.wgsl", folder, index)
+    }
+}
+
+/// Reads the file of the path relative to the directory.
+pub(crate) fn read(dir: &Path, path: &str) -> Result<Vec<u8>, Error> {
+    fs::read(dir.join(path)).map_err(|err| with_path(err, path))
+}
+
+pub(crate) fn read_manifest(dir: &Path) -> Result<Manifest, Error> {
+    let manifest = String::from_utf8(read(dir, MANIFEST)?).map_err(|err| err.utf8_error())?;
+
+    toml::from_str(&manifest).map_err(|err| Error::Parse(format!("{}: {}", MANIFEST, err)))
+}
+
+/// Writes the file of the path relative to the directory, creating its parent directories.
+pub(crate) fn write(dir: &Path, path: &str, contents: &[u8]) -> Result<(), Error> {
+    let path_buf = dir.join(path);
+
+    if let Some(parent) = path_buf.parent() {
+        fs::create_dir_all(parent).map_err(|err| with_path(err, path))?;
+    }
+
+    fs::write(path_buf, contents).map_err(|err| with_path(err, path))
+}
+
+pub(crate) fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<(), Error> {
+    let manifest =
+        toml::to_string(manifest).map_err(|err| Error::Parse(format!("{}: {}", MANIFEST, err)))?;
+
+    write(dir, MANIFEST, manifest.as_bytes())
+}
+
+/// Adds the path to an I/O error, which doesn't tell which file it is about.
+fn with_path(err: io::Error, path: &str) -> Error {
+    Error::Io(io::Error::new(err.kind(), format!("{}: {}", path, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, Library, Pass, PassInput, Texture, TextureEncoding, WgsData};
+    use std::{fs, path::PathBuf};
+
+    /// A directory of its own for the test, emptied.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wgs_core_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        dir
+    }
+
+    #[test]
+    fn project_round_trips() {
+        let mut wgs = WgsData::new("dir", "fn main_image() {}");
+        wgs.add_texture(1, 1, vec![1, 2, 3, 4]).unwrap();
+        wgs.set_texture_encoding(0, TextureEncoding::Png).unwrap();
+        wgs.set_texture_name(0, Some("noise")).unwrap();
+        wgs.set_thumbnail(Some(Texture::new(1, 1, vec![5, 6, 7, 8]).unwrap()));
+        let mut pass = Pass::new("buf", "fn main_image() {}");
+        pass.inputs = vec![PassInput::Texture(0)];
+        wgs.add_pass(pass);
+        let mut library = Library::new();
+        library.insert("module", "fn f() {}");
+        wgs.set_library(library);
+
+        let dir = temp_dir("round_trip");
+        wgs.to_dir(&dir).unwrap();
+        let loaded = WgsData::from_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.name(), "dir");
+        assert_eq!(loaded.frag(), wgs.frag());
+        assert_eq!(loaded.passes_ref(), wgs.passes_ref());
+        assert_eq!(loaded.library_ref(), wgs.library_ref());
+
+        let texture = loaded.texture(0).unwrap();
+        assert_eq!(texture.data, [1, 2, 3, 4]);
+        assert_eq!(texture.encoding, TextureEncoding::Png);
+        assert_eq!(texture.name.as_deref(), Some("noise"));
+        assert_eq!(loaded.thumbnail_ref().unwrap().data, [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        let dir = temp_dir("missing_file");
+        WgsData::new("dir", "fn main_image() {}")
+            .to_dir(&dir)
+            .unwrap();
+        fs::remove_file(dir.join(super::FRAG)).unwrap();
+
+        let result = WgsData::from_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(Error::Io(_))), "{:?}", result);
+    }
+}
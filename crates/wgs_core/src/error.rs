@@ -0,0 +1,126 @@
+use crate::{checksum::ChecksumError, options::LimitError};
+use std::{fmt, io, str::Utf8Error};
+
+/// The error of wgs_core.
+#[derive(Debug)]
+pub enum Error {
+    /// The checksum of a wgs file doesn't match its contents.
+    Checksum(ChecksumError),
+    /// A texture can't be encoded or decoded.
+    Codec(String),
+    /// There is no texture, buffer pass or parameter at the index.
+    IndexOutOfRange { index: usize, len: usize },
+    /// The data doesn't start with the magic bytes of any layout of wgs.
+    InvalidMagic,
+    /// A preprocessor directive of a frag is malformed or unbalanced, for example
+    /// an `#ifdef` without `#endif`.
+    InvalidDirective(String),
+    /// An `#include` directive can't be resolved, for example the modules include each other
+    /// in a cycle.
+    InvalidInclude(String),
+    /// A parameter or the value given to it is invalid.
+    InvalidParam(String),
+    /// The buffer passes are invalid, for example they read each other in a cycle.
+    InvalidPasses(String),
+    /// A texture is invalid, for example its name is taken by another texture.
+    InvalidTexture(String),
+    /// A frag or a name is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// The reader or the writer failed.
+    Io(io::Error),
+    /// A wgs file exceeds one of the limits of [`LoadOptions`](crate::LoadOptions).
+    Limit(LimitError),
+    /// The data is malformed.
+    Parse(String),
+    /// The length of the pixel data doesn't match the size of a texture.
+    SizeMismatch { width: u32, height: u32, len: usize },
+    /// No more texture can be added.
+    TooManyTextures { max: usize },
+    /// The data ends unexpectedly. The wgs file may be truncated.
+    Truncated,
+    /// The wgs file is of a version this crate can't read.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Checksum(err) => err.fmt(f),
+            Self::Codec(message) => write!(f, "Invalid texture: {}", message),
+            Self::IndexOutOfRange { index, len } => {
+                write!(f, "Index {} out of range for length {}", index, len)
+            }
+            Self::InvalidMagic => write!(f, "Not a wgs file"),
+            Self::InvalidDirective(message)
+            | Self::InvalidInclude(message)
+            | Self::InvalidParam(message)
+            | Self::InvalidPasses(message)
+            | Self::InvalidTexture(message)
+            | Self::Parse(message) => write!(f, "{}", message),
+            Self::InvalidUtf8(err) => write!(f, "Invalid UTF-8: {}", err),
+            Self::Io(err) => err.fmt(f),
+            Self::Limit(err) => err.fmt(f),
+            Self::SizeMismatch { width, height, len } => write!(
+                f,
+                "Texture of {}x{} expects 8bit RGBA data, found {} bytes",
+                width, height, len
+            ),
+            Self::TooManyTextures { max } => write!(f, "No more than {} textures allowed", max),
+            Self::Truncated => write!(f, "Unexpected end of file. The wgs file may be truncated"),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported wgs version {}", version),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUtf8(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<binrw::Error> for Error {
+    fn from(err: binrw::Error) -> Self {
+        if err.is_eof() {
+            return Self::Truncated;
+        }
+
+        match err {
+            binrw::Error::Backtrace(backtrace) => Self::from(*backtrace.error),
+            binrw::Error::BadMagic { .. } => Self::InvalidMagic,
+            binrw::Error::Custom { pos, err } => match err.downcast::<Self>() {
+                Ok(err) => *err,
+                Err(err) => Self::Parse(format!("{} at {:#x}", err, pos)),
+            },
+            binrw::Error::Io(err) => Self::Io(err),
+            err => Self::Parse(err.to_string()),
+        }
+    }
+}
+
+impl From<ChecksumError> for Error {
+    fn from(err: ChecksumError) -> Self {
+        Self::Checksum(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<LimitError> for Error {
+    fn from(err: LimitError) -> Self {
+        Self::Limit(err)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(err: Utf8Error) -> Self {
+        Self::InvalidUtf8(err)
+    }
+}
@@ -0,0 +1,477 @@
+//! Exports the shaders of wgs to other shading languages, with the cargo feature `export`.
+
+use crate::{
+    validate::{parse_shader, Diagnostic},
+    SourceMap, SourceOrigin, VERT_DEFAULT,
+};
+use std::fmt;
+
+/// A shading language to export the shaders of wgs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportTarget {
+    /// GLSL 4.50 core. Each texture is combined with its sampler into a `sampler2D`.
+    Glsl,
+    /// HLSL of shader model 5.1.
+    Hlsl,
+    /// MSL 2.0.
+    Msl,
+}
+
+/// The shaders of wgs in another shading language, returned by [`export_shader`].
+#[derive(Clone, Debug)]
+pub struct Export {
+    /// The language of the shaders.
+    pub target: ExportTarget,
+    /// The vertex shader, which covers the viewport with a triangle of 3 vertices.
+    pub vertex: ExportedShader,
+    /// The fragment shader.
+    pub fragment: ExportedShader,
+    /// The resources the fragment shader expects, in the order of their groups and bindings.
+    ///
+    /// Resources unused by the shader are listed as well, though the target may have left them out.
+    pub bindings: Vec<ExportBinding>,
+}
+
+/// A shader of an [`Export`].
+#[derive(Clone, Debug)]
+pub struct ExportedShader {
+    /// The source of the shader.
+    pub source: String,
+    /// The name of the entry point, which may differ from `main` if the target reserves it.
+    pub entry_point: String,
+}
+
+/// A resource expected by an exported shader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportBinding {
+    /// The group of the resource in wgs: 0 for the uniforms, the previous frame and
+    /// the parameters, 1 and on for the textures, then the outputs of the buffer passes.
+    pub group: u32,
+    /// The binding of the resource in its group in wgs.
+    pub binding: u32,
+    /// The name of the resource in wgs, e.g. `u` or `texture0`.
+    pub name: String,
+    /// What the resource is.
+    pub kind: BindingKind,
+    /// Where the resource is bound in the target:
+    ///
+    /// - GLSL: the `binding` of the uniform block, or of the `sampler2D`. A sampler shares
+    ///   the binding of the texture before it, as they are combined.
+    /// - HLSL: the register in the space of the group, e.g. `register(t0, space1)`.
+    /// - MSL: the index of the `[[buffer]]`, the `[[texture]]` or the `[[sampler]]`.
+    pub slot: u32,
+}
+
+/// What an [`ExportBinding`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    /// A sampler.
+    Sampler,
+    /// A 2D texture of floats.
+    Texture,
+    /// A uniform buffer of the given size in bytes.
+    Uniform { size: u32 },
+}
+
+/// Translates a complete WGSL fragment shader, e.g. one generated by
+/// [`concat_shader_frag`](crate::concat_shader_frag) or
+/// [`WgsData::shader_frag`](crate::WgsData::shader_frag), along with [`VERT_DEFAULT`]
+/// to the target.
+///
+/// Returns the diagnostics if the shader fails to compile or to be translated.
+/// The whole shader is regarded as [`SourceOrigin::Frag`].
+pub fn export_shader(shader_frag: &str, target: ExportTarget) -> Result<Export, Vec<Diagnostic>> {
+    let mut source_map = SourceMap::default();
+    source_map.push(0..shader_frag.len(), SourceOrigin::Frag, 0, shader_frag);
+
+    export_shader_with_source_map(shader_frag, &source_map, target)
+}
+
+/// Translates a complete WGSL fragment shader along with [`VERT_DEFAULT`] to the target,
+/// mapping the spans of the diagnostics with the [`SourceMap`], e.g. the one returned
+/// by [`WgsData::shader_frag`](crate::WgsData::shader_frag).
+///
+/// Returns the diagnostics if the shader fails to compile or to be translated.
+pub fn export_shader_with_source_map(
+    shader_frag: &str,
+    source_map: &SourceMap,
+    target: ExportTarget,
+) -> Result<Export, Vec<Diagnostic>> {
+    let mut vertex_source_map = SourceMap::default();
+    vertex_source_map.push(
+        0..VERT_DEFAULT.len(),
+        SourceOrigin::BuiltIn,
+        0,
+        VERT_DEFAULT,
+    );
+
+    let vertex = parse_shader(VERT_DEFAULT, &vertex_source_map)?;
+    let vertex = export_module(&vertex, naga::ShaderStage::Vertex, target, &[])?;
+
+    let fragment = parse_shader(shader_frag, source_map)?;
+    let bindings = collect_bindings(&fragment.0, target);
+    let fragment = export_module(&fragment, naga::ShaderStage::Fragment, target, &bindings)?;
+
+    Ok(Export {
+        target,
+        vertex,
+        fragment,
+        bindings,
+    })
+}
+
+impl fmt::Display for Export {
+    /// Lists the bindings, one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for binding in &self.bindings {
+            let kind = match binding.kind {
+                BindingKind::Sampler => "sampler".to_owned(),
+                BindingKind::Texture => "texture".to_owned(),
+                BindingKind::Uniform { size } => format!("uniform of {} bytes", size),
+            };
+
+            let slot = match (self.target, binding.kind) {
+                (ExportTarget::Glsl, BindingKind::Sampler) => {
+                    format!("combined into layout(binding = {})", binding.slot)
+                }
+                (ExportTarget::Glsl, _) => format!("layout(binding = {})", binding.slot),
+                (ExportTarget::Hlsl, kind) => format!(
+                    "register({}{}, space{})",
+                    match kind {
+                        BindingKind::Sampler => 's',
+                        BindingKind::Texture => 't',
+                        BindingKind::Uniform { .. } => 'b',
+                    },
+                    binding.slot,
+                    binding.group
+                ),
+                (ExportTarget::Msl, kind) => format!(
+                    "[[{}({})]]",
+                    match kind {
+                        BindingKind::Sampler => "sampler",
+                        BindingKind::Texture => "texture",
+                        BindingKind::Uniform { .. } => "buffer",
+                    },
+                    binding.slot
+                ),
+            };
+
+            writeln!(
+                f,
+                "@group({}) @binding({}) {}: {} -> {}",
+                binding.group, binding.binding, binding.name, kind, slot
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lists the resources of the module and assigns their slots in the target.
+fn collect_bindings(module: &naga::Module, target: ExportTarget) -> Vec<ExportBinding> {
+    let mut bindings = vec![];
+
+    for (_, global) in module.global_variables.iter() {
+        let Some(resource) = &global.binding else {
+            continue;
+        };
+
+        let kind = match module.types[global.ty].inner {
+            naga::TypeInner::Sampler { .. } => BindingKind::Sampler,
+            naga::TypeInner::Image { .. } => BindingKind::Texture,
+            ref inner => BindingKind::Uniform {
+                size: inner.size(module.to_ctx()),
+            },
+        };
+
+        bindings.push(ExportBinding {
+            group: resource.group,
+            binding: resource.binding,
+            name: global.name.clone().unwrap_or_default(),
+            kind,
+            slot: 0,
+        });
+    }
+
+    bindings.sort_by_key(|binding| (binding.group, binding.binding));
+
+    let (mut samplers, mut textures, mut uniforms) = (0u32, 0u32, 0u32);
+
+    for binding in &mut bindings {
+        binding.slot = match (target, binding.kind) {
+            (ExportTarget::Hlsl, _) => binding.binding,
+            // Combined with the texture before it, whose slot is the last one taken.
+            (ExportTarget::Glsl, BindingKind::Sampler) => textures.saturating_sub(1),
+            (_, BindingKind::Sampler) => next(&mut samplers),
+            (_, BindingKind::Texture) => next(&mut textures),
+            (_, BindingKind::Uniform { .. }) => next(&mut uniforms),
+        };
+    }
+
+    bindings
+}
+
+/// Translates the module of the stage to the target, binding the resources to their slots.
+fn export_module(
+    (module, info): &(naga::Module, naga::valid::ModuleInfo),
+    stage: naga::ShaderStage,
+    target: ExportTarget,
+    bindings: &[ExportBinding],
+) -> Result<ExportedShader, Vec<Diagnostic>> {
+    let to_diagnostics = |err: &dyn std::error::Error| vec![Diagnostic::new(&err.to_string())];
+
+    let resource = |binding: &ExportBinding| naga::ResourceBinding {
+        group: binding.group,
+        binding: binding.binding,
+    };
+
+    Ok(match target {
+        ExportTarget::Glsl => {
+            let options = naga::back::glsl::Options {
+                version: naga::back::glsl::Version::Desktop(450),
+                binding_map: bindings
+                    .iter()
+                    .filter(|binding| binding.kind != BindingKind::Sampler)
+                    .map(|binding| Ok((resource(binding), to_u8(binding.slot, "slot", binding)?)))
+                    .collect::<Result<_, Vec<Diagnostic>>>()?,
+                ..Default::default()
+            };
+
+            let pipeline_options = naga::back::glsl::PipelineOptions {
+                shader_stage: stage,
+                entry_point: "main".to_owned(),
+                multiview: None,
+            };
+
+            let mut source = String::new();
+            naga::back::glsl::Writer::new(
+                &mut source,
+                module,
+                info,
+                &options,
+                &pipeline_options,
+                naga::proc::BoundsCheckPolicies::default(),
+            )
+            .and_then(|mut writer| writer.write())
+            .map_err(|err| to_diagnostics(&err))?;
+
+            ExportedShader {
+                source,
+                entry_point: "main".to_owned(),
+            }
+        }
+        ExportTarget::Hlsl => {
+            let options = naga::back::hlsl::Options {
+                binding_map: bindings
+                    .iter()
+                    .map(|binding| {
+                        Ok((
+                            resource(binding),
+                            naga::back::hlsl::BindTarget {
+                                space: to_u8(binding.group, "group", binding)?,
+                                register: binding.slot,
+                                binding_array_size: None,
+                            },
+                        ))
+                    })
+                    .collect::<Result<_, Vec<Diagnostic>>>()?,
+                fake_missing_bindings: false,
+                ..Default::default()
+            };
+
+            let mut source = String::new();
+            let reflection = naga::back::hlsl::Writer::new(&mut source, &options)
+                .write(module, info, None)
+                .map_err(|err| to_diagnostics(&err))?;
+
+            ExportedShader {
+                source,
+                entry_point: entry_point(reflection.entry_point_names)?,
+            }
+        }
+        ExportTarget::Msl => {
+            let resources = bindings
+                .iter()
+                .map(|binding| {
+                    let slot = to_u8(binding.slot, "slot", binding)?;
+
+                    let mut bind_target = naga::back::msl::BindTarget::default();
+                    match binding.kind {
+                        BindingKind::Sampler => {
+                            bind_target.sampler =
+                                Some(naga::back::msl::BindSamplerTarget::Resource(slot))
+                        }
+                        BindingKind::Texture => bind_target.texture = Some(slot),
+                        BindingKind::Uniform { .. } => bind_target.buffer = Some(slot),
+                    }
+
+                    Ok((resource(binding), bind_target))
+                })
+                .collect::<Result<_, Vec<Diagnostic>>>()?;
+
+            let options = naga::back::msl::Options {
+                lang_version: (2, 0),
+                per_entry_point_map: [(
+                    "main".to_owned(),
+                    naga::back::msl::EntryPointResources {
+                        resources,
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+                fake_missing_bindings: false,
+                ..Default::default()
+            };
+
+            let (source, translation) = naga::back::msl::write_string(
+                module,
+                info,
+                &options,
+                &naga::back::msl::PipelineOptions::default(),
+            )
+            .map_err(|err| to_diagnostics(&err))?;
+
+            ExportedShader {
+                source,
+                entry_point: entry_point(translation.entry_point_names)?,
+            }
+        }
+    })
+}
+
+/// Returns the name of the only entry point given by the backend.
+fn entry_point<E: std::error::Error>(
+    names: Vec<Result<String, E>>,
+) -> Result<String, Vec<Diagnostic>> {
+    match names.into_iter().next() {
+        Some(Ok(name)) => Ok(name),
+        Some(Err(err)) => Err(vec![Diagnostic::new(&err.to_string())]),
+        None => Err(vec![Diagnostic::new("No entry point found")]),
+    }
+}
+
+/// Returns the value of the counter and increments it.
+fn next(counter: &mut u32) -> u32 {
+    *counter += 1;
+    *counter - 1
+}
+
+/// Narrows the group or the slot of the binding to the `u8` the backend takes.
+fn to_u8(value: u32, what: &str, binding: &ExportBinding) -> Result<u8, Vec<Diagnostic>> {
+    u8::try_from(value).map_err(|_| {
+        vec![Diagnostic::new(&format!(
+            "The {} of `{}` is {}, more than the target supports",
+            what, binding.name, value
+        ))]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WgsData;
+
+    /// Exports the image of wgs data of two textures to the target.
+    fn export_two_textures(target: ExportTarget) -> Export {
+        let mut wgs = WgsData::new(
+            "export",
+            "fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {
+                return textureSample(texture1, sampler1, p);
+            }",
+        );
+        wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+        wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+
+        let (shader, source_map) = wgs.shader_frag().unwrap();
+        export_shader_with_source_map(&shader, &source_map, target).unwrap()
+    }
+
+    /// Returns the slot of each binding, in order.
+    fn slots(export: &Export) -> Vec<u32> {
+        export.bindings.iter().map(|binding| binding.slot).collect()
+    }
+
+    #[test]
+    fn bindings_are_listed_in_order() {
+        let export = export_two_textures(ExportTarget::Msl);
+        let bindings: Vec<_> = export
+            .bindings
+            .iter()
+            .map(|binding| {
+                (
+                    binding.group,
+                    binding.binding,
+                    binding.name.as_str(),
+                    binding.kind,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            bindings,
+            [
+                (0, 0, "u", BindingKind::Uniform { size: 48 }),
+                (0, 1, "previous_frame", BindingKind::Texture),
+                (0, 2, "previous_frame_sampler", BindingKind::Sampler),
+                (1, 0, "texture0", BindingKind::Texture),
+                (1, 1, "sampler0", BindingKind::Sampler),
+                (2, 0, "texture1", BindingKind::Texture),
+                (2, 1, "sampler1", BindingKind::Sampler),
+            ]
+        );
+    }
+
+    #[test]
+    fn glsl_combines_each_sampler_with_its_texture() {
+        let export = export_two_textures(ExportTarget::Glsl);
+
+        assert_eq!(slots(&export), [0, 0, 0, 1, 1, 2, 2]);
+        assert!(export.fragment.source.contains("layout(binding = 2)"));
+    }
+
+    #[test]
+    fn hlsl_keeps_the_bindings_in_the_space_of_the_group() {
+        let export = export_two_textures(ExportTarget::Hlsl);
+
+        assert_eq!(slots(&export), [0, 1, 2, 0, 1, 0, 1]);
+        assert!(export.fragment.source.contains("register(t0, space2)"));
+    }
+
+    #[test]
+    fn msl_counts_each_kind_apart() {
+        let export = export_two_textures(ExportTarget::Msl);
+
+        assert_eq!(slots(&export), [0, 0, 0, 1, 1, 2, 2]);
+        assert!(export.fragment.source.contains("[[texture(2)]]"));
+    }
+
+    #[test]
+    fn diagnostics_point_into_the_frag() {
+        let wgs = WgsData::new(
+            "export",
+            "fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {\n    return q;\n}",
+        );
+        let (shader, source_map) = wgs.shader_frag().unwrap();
+
+        let diagnostics =
+            export_shader_with_source_map(&shader, &source_map, ExportTarget::Glsl).unwrap_err();
+        let label = &diagnostics[0].labels[0];
+
+        assert_eq!(label.origin, SourceOrigin::Frag);
+        assert_eq!((label.line, label.column), (2, 12));
+    }
+
+    #[test]
+    fn slots_beyond_u8_are_diagnosed() {
+        let binding = ExportBinding {
+            group: 256,
+            binding: 0,
+            name: "buf".to_owned(),
+            kind: BindingKind::Texture,
+            slot: 0,
+        };
+
+        assert_eq!(to_u8(255, "group", &binding).ok(), Some(255));
+        assert!(to_u8(binding.group, "group", &binding).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+/// A registry of named WGSL modules, which frags pull in with a line of `#include "name"`.
+///
+/// A library can be embedded in wgs data, see [`WgsData::set_library`](crate::WgsData::set_library),
+/// or provided by the host, see [`WgsData::shader_frag_with_library`](crate::WgsData::shader_frag_with_library).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(transparent)
+)]
+pub struct Library {
+    modules: BTreeMap<String, String>,
+}
+
+impl Library {
+    /// Creates an empty library.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the source of the module of the name, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.modules.get(name).map(String::as_str)
+    }
+
+    /// Adds a module, returning the source of the module it replaces, if any.
+    pub fn insert(&mut self, name: &str, source: &str) -> Option<String> {
+        self.modules.insert(name.to_owned(), source.to_owned())
+    }
+
+    /// Whether the library holds no module.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Iterates over the names and the sources of the modules, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.modules
+            .iter()
+            .map(|(name, source)| (name.as_str(), source.as_str()))
+    }
+
+    /// Returns the number of modules.
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Removes the module of the name, returning its source, if any.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.modules.remove(name)
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{texture::Texture, Error, WgsData, VERSION};
+use binrw::{binread, helpers::count, BinRead, NullString};
+use std::{io, ops::Range};
+
+/// The layout of the very first wgs files, which had no `version` field.
+#[binread]
+#[br(little)]
+struct LegacyWgsData {
+    name: NullString,
+    #[br(temp)]
+    texture_count: u8,
+    frag: NullString,
+    #[br(parse_with = count(texture_count.into()))]
+    textures: Vec<Texture>,
+}
+
+/// The changes made when upgrading legacy wgs data to the current version.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Migration {
+    /// The changes in the order they were applied.
+    pub changes: Vec<MigrationChange>,
+}
+
+/// A single change made by a [`Migration`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MigrationChange {
+    /// The missing `version` field was inserted with the given version.
+    InsertVersion(u32),
+    /// A call to the legacy `texture` built-in function was rewritten to `image`.
+    /// Holds the byte range of the identifier in the original frag.
+    RenameTextureCall(Range<usize>),
+}
+
+/// Reads wgs data in the legacy pre-version layout and upgrades it to the current version.
+pub(crate) fn load_legacy(
+    reader: &mut (impl io::Read + io::Seek),
+) -> Result<(WgsData, Migration), Error> {
+    let legacy = LegacyWgsData::read(reader)?;
+
+    let mut migration = Migration::default();
+    migration
+        .changes
+        .push(MigrationChange::InsertVersion(VERSION));
+
+    let (frag, renamed) = migrate_legacy_frag(&legacy.frag.to_string());
+    migration
+        .changes
+        .extend(renamed.into_iter().map(MigrationChange::RenameTextureCall));
+
+    let mut wgs = WgsData::new(&legacy.name.to_string(), &frag);
+    for texture in legacy.textures {
+        wgs.add_texture(texture.width, texture.height, texture.data)?;
+    }
+
+    Ok((wgs, migration))
+}
+
+/// Rewrites the calls to the legacy `texture` built-in function to `image`.
+///
+/// Returns the migrated frag and the byte ranges of the rewritten identifiers in the original one.
+/// Comments are left untouched.
+pub fn migrate_legacy_frag(frag: &str) -> (String, Vec<Range<usize>>) {
+    const LEGACY: &str = "texture";
+    const CURRENT: &str = "image";
+
+    let bytes = frag.as_bytes();
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+
+    let mut output = String::with_capacity(frag.len());
+    let mut renamed = vec![];
+    let mut copied = 0;
+    let mut index = 0;
+    let mut comment_depth = 0;
+
+    while index < bytes.len() {
+        let rest = &bytes[index..];
+
+        if comment_depth > 0 {
+            if rest.starts_with(b"*/") {
+                comment_depth -= 1;
+                index += 2;
+            } else if rest.starts_with(b"/*") {
+                comment_depth += 1;
+                index += 2;
+            } else {
+                index += 1;
+            }
+            continue;
+        }
+
+        if rest.starts_with(b"//") {
+            index += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+            continue;
+        }
+
+        if rest.starts_with(b"/*") {
+            comment_depth += 1;
+            index += 2;
+            continue;
+        }
+
+        if !is_ident(bytes[index]) {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        while index < bytes.len() && is_ident(bytes[index]) {
+            index += 1;
+        }
+
+        if &frag[start..index] != LEGACY {
+            continue;
+        }
+
+        let is_call = bytes[index..]
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|&b| b == b'(');
+
+        if is_call {
+            output.push_str(&frag[copied..start]);
+            output.push_str(CURRENT);
+            copied = index;
+
+            renamed.push(start..index);
+        }
+    }
+
+    output.push_str(&frag[copied..]);
+
+    (output, renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_calls_are_renamed() {
+        let frag = "let a = texture(t, s, uv);\nlet b = texture (t, s, uv);";
+
+        let (migrated, renamed) = migrate_legacy_frag(frag);
+        assert_eq!(
+            migrated,
+            "let a = image(t, s, uv);\nlet b = image (t, s, uv);"
+        );
+        assert_eq!(renamed, [8..15, 35..42]);
+    }
+
+    #[test]
+    fn other_identifiers_and_comments_are_kept() {
+        let frag = "var t: texture_2d<f32>;\n// texture(t)\n/* /* texture(t) */ texture(t) */\nlet texture = textureSample(t, s, uv);";
+
+        let (migrated, renamed) = migrate_legacy_frag(frag);
+        assert_eq!(migrated, frag);
+        assert!(renamed.is_empty());
+    }
+}
@@ -1,39 +1,147 @@
 //! The core implementation of a wgs file.
 
+mod builder;
+mod checksum;
+mod chunk;
+mod codec;
 mod data;
+#[cfg(feature = "dir")]
+mod dir;
+mod error;
+#[cfg(feature = "export")]
+mod export;
+mod include;
+mod legacy;
 mod meta;
+mod options;
+mod param;
+mod pass;
+mod preprocess;
+#[cfg(feature = "validate")]
+mod reflect;
+mod sampler;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "shadertoy")]
+mod shadertoy;
+mod source_map;
 mod texture;
+mod token;
+mod v1;
+#[cfg(feature = "validate")]
+mod validate;
 
+pub use builder::WgsDataBuilder;
+pub use checksum::ChecksumError;
 pub use data::WgsData;
+pub use error::Error;
+#[cfg(feature = "export")]
+pub use export::{
+    export_shader, export_shader_with_source_map, BindingKind, Export, ExportBinding, ExportTarget,
+    ExportedShader,
+};
+pub use include::Library;
+pub use legacy::{migrate_legacy_frag, Migration, MigrationChange};
+pub use options::{LimitError, LoadOptions};
+pub use param::{pack_params, Param, ParamKind, ParamValue};
+pub use pass::{Pass, PassInput};
+use preprocess::Piece;
+#[cfg(feature = "validate")]
+pub use reflect::{reflect_shader, reflect_shader_with_source_map, Reflection};
+pub use sampler::{AddressMode, FilterMode, TextureSampler};
+#[cfg(feature = "shadertoy")]
+pub use shadertoy::import_shadertoy;
+use source_map::ShaderBuilder;
+pub use source_map::{SourceLocation, SourceMap, SourceOrigin};
+pub use texture::{Texture, TextureEncoding};
+#[cfg(feature = "validate")]
+pub use validate::{validate_shader, validate_shader_with_source_map, Diagnostic, Label};
 
 /// The extension of a wgs file.
-pub const EXTENSION: &'static str = "wgs";
-/// The version of a wgs file.
-pub const VERSION: u32 = 1;
+pub const EXTENSION: &str = "wgs";
+/// The latest version of a wgs file, which is used when saving.
+pub const VERSION: u32 = 2;
 
 /// The content of the default editable part in a fragment shader for a wgs file.
-pub const FRAG_DEFAULT: &'static str = include_str!("./assets/frag.default.wgsl");
+pub const FRAG_DEFAULT: &str = include_str!("./assets/frag.default.wgsl");
 /// The content of the default vertex shader for a wgs file.
-pub const VERT_DEFAULT: &'static str = include_str!("./assets/vert.wgsl");
+pub const VERT_DEFAULT: &str = include_str!("./assets/vert.wgsl");
 
-const FRAG_PREFIX: &'static str = include_str!("./assets/frag.prefix.wgsl");
+const FRAG_PREFIX: &str = include_str!("./assets/frag.prefix.wgsl");
+const FRAG_PASS_SUFFIX: &str = include_str!("./assets/frag.pass.suffix.wgsl");
 #[cfg(target_arch = "wasm32")]
-const FRAG_SUFFIX: &'static str = include_str!("./assets/frag.suffix.gl.wgsl");
+const FRAG_SUFFIX: &str = include_str!("./assets/frag.suffix.gl.wgsl");
 #[cfg(not(target_arch = "wasm32"))]
-const FRAG_SUFFIX: &'static str = include_str!("./assets/frag.suffix.wgsl");
+const FRAG_SUFFIX: &str = include_str!("./assets/frag.suffix.wgsl");
 
 /// A util function helps to generate a complete fragment shader.
 pub fn concat_shader_frag(main_image: &str, texture_count: usize) -> String {
+    concat_shader_frag_with_source_map(main_image, texture_count).0
+}
+
+/// Generates a complete fragment shader like [`concat_shader_frag`], along with a [`SourceMap`]
+/// which maps the ranges of the shader back to `main_image` or the built-in code.
+pub fn concat_shader_frag_with_source_map(
+    main_image: &str,
+    texture_count: usize,
+) -> (String, SourceMap) {
+    assemble_shader(
+        &declare_textures(
+            (0..texture_count)
+                .map(|index| (format!("texture{}", index), format!("sampler{}", index))),
+        ),
+        &[Piece::slice(
+            main_image,
+            0..main_image.len(),
+            SourceOrigin::Frag,
+        )],
+        FRAG_SUFFIX,
+    )
+}
+
+/// Puts the built-in code, the declarations and the pieces of the editable part together.
+fn assemble_shader(declarations: &str, frag: &[Piece], suffix: &str) -> (String, SourceMap) {
+    let mut builder = ShaderBuilder::default();
+    builder.push(FRAG_PREFIX, SourceOrigin::BuiltIn);
+    builder.push("\n", SourceOrigin::BuiltIn);
+    builder.push(declarations, SourceOrigin::BuiltIn);
+    builder.push("\n", SourceOrigin::BuiltIn);
+    for piece in frag {
+        builder.push_mapped(
+            &piece.text,
+            piece.source,
+            piece.offset,
+            piece.origin.clone(),
+        );
+    }
+    builder.push("\n", SourceOrigin::BuiltIn);
+    builder.push(suffix, SourceOrigin::BuiltIn);
+
+    builder.finish()
+}
+
+/// Declares the textures and their samplers in the group, binding them in pairs.
+fn declare_inputs(group: usize, inputs: impl Iterator<Item = (String, String)>) -> String {
+    let mut declarations = String::new();
+    for (index, (name, sampler)) in inputs.enumerate() {
+        declarations.push_str(&format!("@group({}) @binding({})\n", group, index * 2));
+        declarations.push_str(&format!("var {}: texture_2d<f32>;\n", name));
+        declarations.push_str(&format!("@group({}) @binding({})\n", group, index * 2 + 1));
+        declarations.push_str(&format!("var {}: sampler;\n", sampler));
+    }
+
+    declarations
+}
+
+/// Declares each texture and its sampler in a group of its own, starting from group 1.
+fn declare_textures(textures: impl Iterator<Item = (String, String)>) -> String {
     let mut texture2ds = String::new();
-    for index in 0..texture_count {
+    for (index, (name, sampler)) in textures.enumerate() {
         texture2ds.push_str(&format!("@group({}) @binding(0)\n", index + 1,));
-        texture2ds.push_str(&format!("var texture{}: texture_2d<f32>;\n", index));
+        texture2ds.push_str(&format!("var {}: texture_2d<f32>;\n", name));
         texture2ds.push_str(&format!("@group({}) @binding(1)\n", index + 1,));
-        texture2ds.push_str(&format!("var sampler{}: sampler;\n", index));
+        texture2ds.push_str(&format!("var {}: sampler;\n", sampler));
     }
 
-    format!(
-        "{}\n{}\n{}\n{}",
-        FRAG_PREFIX, texture2ds, main_image, FRAG_SUFFIX
-    )
+    texture2ds
 }
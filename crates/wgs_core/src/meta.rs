@@ -5,8 +5,10 @@ use binrw::{binrw, NullString};
 #[derive(Debug)]
 #[binrw]
 #[brw(little)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Meta {
     /// The name of the wgs file. Not filename.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::null_string"))]
     pub name: NullString,
     /// The count of textures embedded in the wgs file.
     pub texture_count: u8,
@@ -25,3 +27,28 @@ impl Meta {
         }
     }
 }
+
+/// The optional descriptive info of a wgs file, stored in an `INFO` chunk since wgs 2.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Metadata {
+    pub author: String,
+    pub description: String,
+    pub license: String,
+    pub source_url: String,
+    pub tags: Vec<String>,
+    /// In seconds since the Unix epoch.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub created: Option<u64>,
+    /// In seconds since the Unix epoch.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub modified: Option<u64>,
+}
+
+impl Metadata {
+    /// Returns whether nothing is set, in which case no `INFO` chunk is saved.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,209 @@
+use crate::codec::texture_len;
+use std::fmt;
+
+/// The options of [`WgsData::load_with_options`](crate::WgsData::load_with_options).
+///
+/// The limits protect against hostile or oversized files. Nothing is read beyond the end
+/// of the file, and the textures are checked before they are decoded, so loading takes
+/// at most the size of the file on top of the limits.
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// Whether to check the checksum of the file, if it has one. `true` by default.
+    ///
+    /// Turning it off lets recovery tools read what they can out of a corrupted file.
+    pub verify_checksum: bool,
+    /// The maximum length in bytes of the frag of the image or a buffer pass. 1 MiB by default.
+    pub max_frag_len: usize,
+    /// The maximum length in bytes of the name of the file, a buffer pass or a parameter.
+    /// 1 KiB by default.
+    pub max_name_len: usize,
+    /// The maximum width and height of a texture or the thumbnail. 8192 by default,
+    /// which is also the default limit of wgpu.
+    pub max_texture_dimension: u32,
+    /// The maximum number of bytes of all the decoded textures and the thumbnail together.
+    /// 512 MiB by default.
+    pub max_total_bytes: u64,
+}
+
+/// The error returned when a wgs file exceeds one of the limits of [`LoadOptions`].
+///
+/// Returned as [`Error::Limit`](crate::Error::Limit).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    /// A frag is longer than [`LoadOptions::max_frag_len`].
+    FragLen { len: usize, max: usize },
+    /// A name is longer than [`LoadOptions::max_name_len`].
+    NameLen { len: usize, max: usize },
+    /// A texture is wider or higher than [`LoadOptions::max_texture_dimension`].
+    TextureDimension { width: u32, height: u32, max: u32 },
+    /// The textures take more than [`LoadOptions::max_total_bytes`].
+    TotalBytes { max: u64 },
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            verify_checksum: true,
+            max_frag_len: 1 << 20,
+            max_name_len: 1 << 10,
+            max_texture_dimension: 8192,
+            max_total_bytes: 512 << 20,
+        }
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FragLen { len, max } => {
+                write!(f, "Frag of {} bytes exceeds the limit of {}", len, max)
+            }
+            Self::NameLen { len, max } => {
+                write!(f, "Name of {} bytes exceeds the limit of {}", len, max)
+            }
+            Self::TextureDimension { width, height, max } => write!(
+                f,
+                "Texture of {}x{} exceeds the limit of {}x{}",
+                width, height, max, max
+            ),
+            Self::TotalBytes { max } => {
+                write!(f, "Textures exceed the limit of {} bytes in total", max)
+            }
+        }
+    }
+}
+
+/// Keeps track of the limits of [`LoadOptions`] while loading.
+pub(crate) struct Budget<'a> {
+    options: &'a LoadOptions,
+    total_bytes: u64,
+}
+
+impl<'a> Budget<'a> {
+    pub fn new(options: &'a LoadOptions) -> Self {
+        Self {
+            options,
+            total_bytes: 0,
+        }
+    }
+
+    pub fn check_frag(&self, frag: &[u8]) -> Result<(), LimitError> {
+        if frag.len() > self.options.max_frag_len {
+            return Err(LimitError::FragLen {
+                len: frag.len(),
+                max: self.options.max_frag_len,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn check_name(&self, name: &[u8]) -> Result<(), LimitError> {
+        if name.len() > self.options.max_name_len {
+            return Err(LimitError::NameLen {
+                len: name.len(),
+                max: self.options.max_name_len,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Takes the bytes of a texture of the given size out of the budget.
+    /// Needs to be called before decoding the texture.
+    pub fn take_texture(&mut self, width: u32, height: u32) -> Result<(), LimitError> {
+        let max = self.options.max_texture_dimension;
+        if width > max || height > max {
+            return Err(LimitError::TextureDimension { width, height, max });
+        }
+
+        let max = self.options.max_total_bytes;
+        self.total_bytes = texture_len(width, height)
+            .and_then(|len| self.total_bytes.checked_add(len))
+            .filter(|total_bytes| *total_bytes <= max)
+            .ok_or(LimitError::TotalBytes { max })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_dimension_is_limited() {
+        let options = LoadOptions {
+            max_texture_dimension: 16,
+            ..Default::default()
+        };
+        let mut budget = Budget::new(&options);
+
+        assert!(budget.take_texture(16, 16).is_ok());
+        assert_eq!(
+            budget.take_texture(17, 1),
+            Err(LimitError::TextureDimension {
+                width: 17,
+                height: 1,
+                max: 16
+            })
+        );
+    }
+
+    #[test]
+    fn total_bytes_add_up() {
+        let options = LoadOptions {
+            max_total_bytes: 64,
+            ..Default::default()
+        };
+        let mut budget = Budget::new(&options);
+
+        assert!(budget.take_texture(2, 4).is_ok());
+        assert!(budget.take_texture(4, 2).is_ok());
+        assert_eq!(
+            budget.take_texture(1, 1),
+            Err(LimitError::TotalBytes { max: 64 })
+        );
+    }
+
+    #[test]
+    fn total_bytes_never_overflow() {
+        let options = LoadOptions {
+            max_texture_dimension: u32::MAX,
+            max_total_bytes: u64::MAX,
+            ..Default::default()
+        };
+        let mut budget = Budget::new(&options);
+
+        assert_eq!(
+            budget.take_texture(u32::MAX, u32::MAX),
+            Err(LimitError::TotalBytes { max: u64::MAX })
+        );
+        assert!(budget.take_texture(u32::MAX, 1 << 30).is_ok());
+        assert_eq!(
+            budget.take_texture(u32::MAX, 1 << 30),
+            Err(LimitError::TotalBytes { max: u64::MAX })
+        );
+    }
+
+    #[test]
+    fn names_and_frags_are_limited() {
+        let options = LoadOptions {
+            max_frag_len: 4,
+            max_name_len: 2,
+            ..Default::default()
+        };
+        let budget = Budget::new(&options);
+
+        assert!(budget.check_frag(b"abcd").is_ok());
+        assert_eq!(
+            budget.check_frag(b"abcde"),
+            Err(LimitError::FragLen { len: 5, max: 4 })
+        );
+        assert!(budget.check_name(b"ab").is_ok());
+        assert_eq!(
+            budget.check_name(b"abc"),
+            Err(LimitError::NameLen { len: 3, max: 2 })
+        );
+    }
+}
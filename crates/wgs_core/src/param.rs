@@ -0,0 +1,342 @@
+use crate::{pass::is_identifier, Error};
+use std::collections::HashSet;
+
+/// The keywords and the reserved words of WGSL, which can't name a field of `Params`.
+const RESERVED: &str = include_str!("./assets/reserved.txt");
+
+/// A parameter of wgs, which can be tweaked at runtime without editing the frag.
+///
+/// The parameters are exposed to WGSL as the fields of the uniform `p`, in order.
+/// See [`ParamKind`] for the WGSL type of each kind.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Param {
+    /// The name of the parameter. Must be a WGSL identifier which is not a keyword
+    /// or a reserved word, and must be unique.
+    pub name: String,
+    /// The type, the default value and the constraints of the parameter.
+    pub kind: ParamKind,
+}
+
+/// The type of a [`Param`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ParamKind {
+    /// A toggle, exposed as `u32` which is `0` or `1`.
+    Bool { default: bool },
+    /// An RGB color, exposed as `vec3<f32>`.
+    Color { default: [f32; 3] },
+    /// One of the named options, exposed as the `u32` index of the option.
+    Enum { default: u32, options: Vec<String> },
+    /// A number within the range, exposed as `f32`.
+    Float { default: f32, min: f32, max: f32 },
+}
+
+/// The value of a [`Param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ParamValue {
+    Bool(bool),
+    Color([f32; 3]),
+    Enum(u32),
+    Float(f32),
+}
+
+impl Param {
+    /// Creates a new parameter.
+    pub fn new(name: &str, kind: ParamKind) -> Self {
+        Self {
+            name: name.to_owned(),
+            kind,
+        }
+    }
+
+    /// Checks the value against the type and the constraints of the parameter.
+    ///
+    /// Returns the value to use, with a float clamped to the range.
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::InvalidParam`] if the value is of another type.
+    /// - Will return [`Error::InvalidParam`] if an enum value is not the index of an option.
+    pub fn check(&self, value: ParamValue) -> Result<ParamValue, Error> {
+        match (&self.kind, value) {
+            (ParamKind::Bool { .. }, ParamValue::Bool(_))
+            | (ParamKind::Color { .. }, ParamValue::Color(_)) => Ok(value),
+            (ParamKind::Enum { options, .. }, ParamValue::Enum(index)) => {
+                if (index as usize) < options.len() {
+                    Ok(value)
+                } else {
+                    Err(Error::InvalidParam(format!(
+                        "Param `{}` has {} options, found option {}",
+                        self.name,
+                        options.len(),
+                        index
+                    )))
+                }
+            }
+            (ParamKind::Float { min, max, .. }, ParamValue::Float(value)) => {
+                Ok(ParamValue::Float(value.max(*min).min(*max)))
+            }
+            _ => Err(Error::InvalidParam(format!(
+                "Param `{}` expects {}, found {:?}",
+                self.name,
+                self.kind.wgsl_type(),
+                value
+            ))),
+        }
+    }
+
+    /// Returns the default value of the parameter.
+    pub fn default_value(&self) -> ParamValue {
+        match &self.kind {
+            ParamKind::Bool { default } => ParamValue::Bool(*default),
+            ParamKind::Color { default } => ParamValue::Color(*default),
+            ParamKind::Enum { default, .. } => ParamValue::Enum(*default),
+            ParamKind::Float { default, .. } => ParamValue::Float(*default),
+        }
+    }
+}
+
+impl ParamKind {
+    fn wgsl_type(&self) -> &'static str {
+        match self {
+            Self::Bool { .. } | Self::Enum { .. } => "u32",
+            Self::Color { .. } => "vec3<f32>",
+            Self::Float { .. } => "f32",
+        }
+    }
+}
+
+impl ParamValue {
+    /// Returns the alignment and the size of the value in a WGSL uniform buffer.
+    fn layout(&self) -> (usize, usize) {
+        match self {
+            Self::Bool(_) | Self::Enum(_) | Self::Float(_) => (4, 4),
+            Self::Color(_) => (16, 12),
+        }
+    }
+}
+
+/// Packs the values of the parameters in the layout of the `Params` struct in WGSL.
+///
+/// The size is rounded up to a multiple of 16 bytes, as required by uniform buffers.
+pub fn pack_params(values: &[ParamValue]) -> Vec<u8> {
+    let mut buffer = vec![];
+
+    for value in values {
+        let (align, _) = value.layout();
+        buffer.resize(buffer.len().next_multiple_of(align), 0);
+
+        match value {
+            ParamValue::Bool(value) => buffer.extend_from_slice(&(*value as u32).to_le_bytes()),
+            ParamValue::Color(value) => {
+                for channel in value {
+                    buffer.extend_from_slice(&channel.to_le_bytes());
+                }
+            }
+            ParamValue::Enum(value) => buffer.extend_from_slice(&value.to_le_bytes()),
+            ParamValue::Float(value) => buffer.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    buffer.resize(buffer.len().next_multiple_of(16).max(16), 0);
+
+    buffer
+}
+
+/// Checks that the parameters are named after distinct WGSL identifiers, that the ranges
+/// of the floats are not empty and that the default values are valid.
+pub(crate) fn check_params(params: &[Param]) -> Result<(), Error> {
+    let mut names = HashSet::new();
+
+    for param in params {
+        if !is_identifier(&param.name) || RESERVED.lines().any(|word| word == param.name) {
+            return Err(Error::InvalidParam(format!(
+                "Param name `{}` is not a valid identifier",
+                param.name
+            )));
+        }
+
+        if !names.insert(param.name.as_str()) {
+            return Err(Error::InvalidParam(format!(
+                "Duplicate param name `{}`",
+                param.name
+            )));
+        }
+
+        if let ParamKind::Float { min, max, .. } = param.kind {
+            if min.is_nan() || max.is_nan() || min > max {
+                return Err(Error::InvalidParam(format!(
+                    "Param `{}` has a min of {} above its max of {}",
+                    param.name, min, max
+                )));
+            }
+        }
+
+        param.check(param.default_value())?;
+    }
+
+    Ok(())
+}
+
+/// Declares the `Params` struct and the uniform `p`. Empty if there is no parameter.
+pub(crate) fn declare_params(params: &[Param]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+
+    let mut declarations = String::from("struct Params {\n");
+    for param in params {
+        declarations.push_str(&format!(
+            "    {}: {},\n",
+            param.name,
+            param.kind.wgsl_type()
+        ));
+    }
+    declarations.push_str("};\n\n@group(0) @binding(3)\nvar<uniform> p: Params;\n");
+
+    declarations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float(name: &str, min: f32, max: f32) -> Param {
+        Param::new(
+            name,
+            ParamKind::Float {
+                default: min,
+                min,
+                max,
+            },
+        )
+    }
+
+    #[test]
+    fn valid_params_pass() {
+        let params = [
+            float("gain", 0.0, 1.0),
+            float("fixed", 1.0, 1.0),
+            Param::new("on", ParamKind::Bool { default: true }),
+        ];
+
+        assert!(check_params(&params).is_ok());
+    }
+
+    #[test]
+    fn invalid_names_fail() {
+        for name in ["a b", "", "1a", "fn", "let", "f32", "texture_2d"] {
+            let result = check_params(&[float(name, 0.0, 1.0)]);
+            assert!(matches!(result, Err(Error::InvalidParam(_))), "{}", name);
+        }
+    }
+
+    #[test]
+    fn duplicate_names_fail() {
+        let params = [float("gain", 0.0, 1.0), float("gain", 0.0, 2.0)];
+
+        assert!(matches!(check_params(&params), Err(Error::InvalidParam(_))));
+    }
+
+    #[test]
+    fn empty_ranges_fail() {
+        for (min, max) in [(1.0, 0.0), (f32::NAN, 1.0), (0.0, f32::NAN)] {
+            let result = check_params(&[float("gain", min, max)]);
+            assert!(
+                matches!(result, Err(Error::InvalidParam(_))),
+                "{}..{}",
+                min,
+                max
+            );
+        }
+    }
+
+    #[test]
+    fn enum_default_out_of_options_fails() {
+        let param = Param::new(
+            "mode",
+            ParamKind::Enum {
+                default: 2,
+                options: vec!["a".to_owned(), "b".to_owned()],
+            },
+        );
+
+        assert!(matches!(
+            check_params(&[param]),
+            Err(Error::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn values_are_packed_like_the_params_struct() {
+        let values = [
+            ParamValue::Float(0.5),
+            ParamValue::Color([1.0, 2.0, 3.0]),
+            ParamValue::Bool(true),
+            ParamValue::Enum(7),
+        ];
+
+        let mut expected = vec![];
+        expected.extend_from_slice(&0.5f32.to_le_bytes());
+        expected.resize(16, 0);
+        for channel in [1.0f32, 2.0, 3.0] {
+            expected.extend_from_slice(&channel.to_le_bytes());
+        }
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(&7u32.to_le_bytes());
+        expected.resize(48, 0);
+
+        assert_eq!(pack_params(&values), expected);
+        assert_eq!(pack_params(&[]), [0; 16]);
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn packing_matches_the_layout_of_naga() {
+        let params = [
+            Param::new("on", ParamKind::Bool { default: true }),
+            Param::new(
+                "tint",
+                ParamKind::Color {
+                    default: [1.0, 2.0, 3.0],
+                },
+            ),
+            float("gain", 0.25, 1.0),
+            Param::new(
+                "more",
+                ParamKind::Color {
+                    default: [4.0, 5.0, 6.0],
+                },
+            ),
+        ];
+
+        let module = naga::front::wgsl::parse_str(&declare_params(&params)).unwrap();
+        let (_, ty) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some("Params"))
+            .unwrap();
+        let naga::TypeInner::Struct { members, span } = &ty.inner else {
+            unreachable!();
+        };
+
+        let values: Vec<_> = params.iter().map(Param::default_value).collect();
+        let packed = pack_params(&values);
+        assert_eq!(packed.len(), *span as usize);
+
+        for (member, value) in members.iter().zip(&values) {
+            let bytes = pack_params(std::slice::from_ref(value));
+            let (_, size) = value.layout();
+            let offset = member.offset as usize;
+
+            assert_eq!(
+                packed[offset..offset + size],
+                bytes[..size],
+                "{:?}",
+                member.name
+            );
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{preprocess::declarations, Param, FRAG_PREFIX};
+use std::collections::HashSet;
+
+/// A buffer pass, which renders into an intermediate texture before the image pass.
+///
+/// Like [`WgsData::frag`](crate::WgsData::frag), the frag of a pass defines a `main_image` function.
+/// The output of the pass, including alpha, is kept in a 16bit float RGBA texture
+/// of the size of the image, which can be read by the other passes and the image pass.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Pass {
+    /// The name of the pass. Must be a WGSL identifier, which is not taken by a declaration
+    /// of the shader, such as `u` or `image`, or by a [`Param`].
+    ///
+    /// The output of the pass is declared as `{name}` and `{name}_sampler` in the shaders reading it.
+    pub name: String,
+    /// The editable part of the fragment shader of the pass.
+    pub frag: String,
+    /// What the pass reads, in the order of their bindings.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inputs: Vec<PassInput>,
+}
+
+/// Something a [`Pass`] reads.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum PassInput {
+    /// The output of the pass of the given name.
+    ///
+    /// A pass reading its own output gets the one of the previous frame.
+    Pass(String),
+    /// The texture of the given index, declared after its [`Texture::name`](crate::Texture::name).
+    Texture(usize),
+}
+
+impl Pass {
+    /// Creates a new pass without inputs.
+    pub fn new(name: &str, frag: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            frag: frag.to_owned(),
+            inputs: vec![],
+        }
+    }
+}
+
+/// Sorts the passes so that each pass comes after the passes it reads.
+///
+/// Returns the indices of the passes in the order to execute them.
+pub(crate) fn sort_passes(
+    passes: &[Pass],
+    params: &[Param],
+    texture_count: usize,
+) -> Result<Vec<usize>, String> {
+    let reserved = reserved_names();
+    let mut names = HashSet::new();
+    for pass in passes {
+        if !is_identifier(&pass.name) {
+            return Err(format!(
+                "Pass name `{}` is not a valid identifier",
+                pass.name
+            ));
+        }
+
+        for name in [pass.name.clone(), format!("{}_sampler", pass.name)] {
+            if reserved.contains(&name) || params.iter().any(|param| param.name == name) {
+                return Err(format!(
+                    "Pass `{}` is declared as `{}`, which is taken",
+                    pass.name, name
+                ));
+            }
+        }
+
+        if !names.insert(pass.name.as_str()) {
+            return Err(format!("Duplicate pass name `{}`", pass.name));
+        }
+    }
+
+    for pass in passes {
+        let mut inputs = HashSet::new();
+        for input in &pass.inputs {
+            match input {
+                PassInput::Pass(name) if !names.contains(name.as_str()) => {
+                    return Err(format!(
+                        "Pass `{}` reads unknown pass `{}`",
+                        pass.name, name
+                    ));
+                }
+                PassInput::Texture(index) if *index >= texture_count => {
+                    return Err(format!(
+                        "Pass `{}` reads unknown texture {}",
+                        pass.name, index
+                    ));
+                }
+                _ => {}
+            }
+
+            if !inputs.insert(input) {
+                return Err(format!("Pass `{}` reads {:?} twice", pass.name, input));
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(passes.len());
+    let mut is_sorted = vec![false; passes.len()];
+
+    while order.len() < passes.len() {
+        let next = (0..passes.len()).find(|&index| {
+            !is_sorted[index]
+                && passes[index].inputs.iter().all(|input| match input {
+                    PassInput::Pass(name) => {
+                        *name == passes[index].name
+                            || passes
+                                .iter()
+                                .zip(&is_sorted)
+                                .any(|(pass, &is_sorted)| is_sorted && pass.name == *name)
+                    }
+                    PassInput::Texture(_) => true,
+                })
+        });
+
+        match next {
+            Some(index) => {
+                is_sorted[index] = true;
+                order.push(index);
+            }
+            None => {
+                let names = passes
+                    .iter()
+                    .zip(&is_sorted)
+                    .filter(|(_, &is_sorted)| !is_sorted)
+                    .map(|(pass, _)| format!("`{}`", pass.name))
+                    .collect::<Vec<_>>();
+
+                return Err(format!("Passes {} read each other", names.join(", ")));
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+/// Returns the names declared by the shader around the frags, which no texture or pass may take.
+pub(crate) fn reserved_names() -> HashSet<String> {
+    declarations(FRAG_PREFIX)
+        .into_iter()
+        .chain(["Params", "main", "p"])
+        .map(str::to_owned)
+        .collect()
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !name.starts_with("__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParamKind;
+
+    fn pass(name: &str, inputs: &[&str]) -> Pass {
+        Pass {
+            inputs: inputs
+                .iter()
+                .map(|name| PassInput::Pass((*name).to_owned()))
+                .collect(),
+            ..Pass::new(name, "")
+        }
+    }
+
+    #[test]
+    fn passes_come_after_their_inputs() {
+        let passes = [
+            pass("blur", &["sharp"]),
+            pass("trail", &["trail", "blur"]),
+            pass("sharp", &[]),
+        ];
+
+        assert_eq!(sort_passes(&passes, &[], 0), Ok(vec![2, 0, 1]));
+    }
+
+    #[test]
+    fn cycles_fail() {
+        let passes = [pass("a", &["b"]), pass("b", &["c"]), pass("c", &["a"])];
+        let err = sort_passes(&passes, &[], 0).unwrap_err();
+
+        assert!(err.contains("`a`, `b`, `c`"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_and_repeated_inputs_fail() {
+        assert!(sort_passes(&[pass("a", &["b"])], &[], 0).is_err());
+        assert!(sort_passes(&[pass("a", &["a", "a"])], &[], 0).is_err());
+
+        let texture = Pass {
+            inputs: vec![PassInput::Texture(1)],
+            ..Pass::new("a", "")
+        };
+        assert!(sort_passes(std::slice::from_ref(&texture), &[], 1).is_err());
+        assert_eq!(sort_passes(&[texture], &[], 2), Ok(vec![0]));
+    }
+
+    #[test]
+    fn taken_names_fail() {
+        for name in [
+            "u",
+            "image",
+            "previous_frame",
+            "main",
+            "p",
+            "Params",
+            "1a",
+            "__a",
+        ] {
+            assert!(sort_passes(&[pass(name, &[])], &[], 0).is_err(), "{}", name);
+        }
+
+        assert!(sort_passes(&[pass("a", &[]), pass("a", &[])], &[], 0).is_err());
+
+        let params = [
+            Param::new("gain", ParamKind::Bool { default: true }),
+            Param::new("blur_sampler", ParamKind::Bool { default: true }),
+        ];
+        assert!(sort_passes(&[pass("gain", &[])], &params, 0).is_err());
+        assert!(sort_passes(&[pass("blur", &[])], &params, 0).is_err());
+        assert!(sort_passes(&[pass("sharp", &[])], &params, 0).is_ok());
+    }
+}
@@ -0,0 +1,512 @@
+//! The preprocessor of the frags, which runs before they are put into the complete shaders.
+//!
+//! It handles the directives below, each on a line of its own:
+//!
+//! - `#include "name"` pulls in the module of the name from a [`Library`].
+//! - `#define NAME` or `#define NAME value` defines a name. A name with a value is replaced
+//!   by the value in the code that follows.
+//! - `#undef NAME` removes the definition of a name.
+//! - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` keep or drop the lines between them.
+//!
+//! The lines in block comments are left as they are.
+
+use crate::{
+    include::Library,
+    pass::is_identifier,
+    token::{comment_depth, tokens, tokens_at_depth},
+    Error, SourceOrigin, VERSION,
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+/// A piece of a shader after preprocessing.
+pub(crate) struct Piece<'a> {
+    pub text: Cow<'a, str>,
+    /// The source the piece maps back to.
+    pub source: &'a str,
+    /// The byte offset in the source the piece maps back to.
+    pub offset: usize,
+    pub origin: SourceOrigin,
+}
+
+impl<'a> Piece<'a> {
+    /// Creates a piece of the source, which maps back to itself.
+    pub fn slice(source: &'a str, range: Range<usize>, origin: SourceOrigin) -> Self {
+        Self {
+            text: Cow::Borrowed(&source[range.clone()]),
+            source,
+            offset: range.start,
+            origin,
+        }
+    }
+}
+
+/// A preprocessor directive.
+enum Directive<'s> {
+    Define(&'s str, &'s str),
+    Else,
+    EndIf,
+    /// `#ifdef` if `true`, `#ifndef` otherwise.
+    IfDef(&'s str, bool),
+    Include(&'s str),
+    Undef(&'s str),
+}
+
+/// An `#ifdef` or an `#ifndef` the preprocessor is in.
+struct Condition {
+    /// Whether the lines of the current branch are kept.
+    is_kept: bool,
+    has_else: bool,
+}
+
+struct Preprocessor<'a, 'l> {
+    libraries: &'l [&'a Library],
+    defines: HashMap<String, String>,
+    pieces: Vec<Piece<'a>>,
+    /// The modules included so far.
+    included: HashSet<&'a str>,
+    /// The modules being preprocessed, from the outermost.
+    stack: Vec<&'a str>,
+    /// Where each top-level name is declared.
+    declarations: HashMap<String, SourceOrigin>,
+}
+
+/// Returns the names wgs defines for the frags:
+///
+/// - `WGS_WEB` on the Web, or `WGS_NATIVE` elsewhere.
+/// - `WGS_VERSION`, the latest version of wgs.
+/// - `WGS_TEXTURE_COUNT`, the number of textures.
+pub(crate) fn builtin_defines(texture_count: usize) -> HashMap<String, String> {
+    let platform = if cfg!(target_arch = "wasm32") {
+        "WGS_WEB"
+    } else {
+        "WGS_NATIVE"
+    };
+
+    HashMap::from([
+        (platform.to_owned(), String::new()),
+        ("WGS_VERSION".to_owned(), VERSION.to_string()),
+        ("WGS_TEXTURE_COUNT".to_owned(), texture_count.to_string()),
+    ])
+}
+
+/// Preprocesses the source with the names defined, looking the included modules up
+/// in the libraries in order, and returns the pieces the shader is made of.
+///
+/// Each module is included once, at its first directive, and is preprocessed as well.
+/// It must not include itself, directly or not. The definitions carry on from a module
+/// to the code following its `#include`.
+pub(crate) fn preprocess<'a>(
+    source: &'a str,
+    origin: SourceOrigin,
+    libraries: &[&'a Library],
+    defines: HashMap<String, String>,
+) -> Result<Vec<Piece<'a>>, Error> {
+    let mut preprocessor = Preprocessor {
+        libraries,
+        defines,
+        pieces: vec![],
+        included: HashSet::new(),
+        stack: vec![],
+        declarations: HashMap::new(),
+    };
+
+    preprocessor.run(source, origin)?;
+
+    Ok(preprocessor.pieces)
+}
+
+impl<'a> Preprocessor<'a, '_> {
+    fn run(&mut self, source: &'a str, origin: SourceOrigin) -> Result<(), Error> {
+        let mut conditions: Vec<Condition> = vec![];
+        // The lines kept, to find the names declared.
+        let mut kept = String::new();
+        let mut end = 0;
+        let mut depth = 0;
+
+        for line in source.split_inclusive('\n') {
+            let start = end;
+            end += line.len();
+
+            let is_kept = conditions.iter().all(|condition| condition.is_kept);
+
+            // A line in a block comment is never a directive.
+            let comment_depth_at_start = depth;
+            depth = comment_depth(line, depth);
+
+            let directive = if comment_depth_at_start > 0 {
+                None
+            } else {
+                parse_directive(line)?
+            };
+
+            let Some(directive) = directive else {
+                if is_kept {
+                    self.push_line(source, start..end, comment_depth_at_start, &origin);
+                    kept.push_str(line);
+                }
+
+                continue;
+            };
+
+            match directive {
+                Directive::IfDef(name, expected) => conditions.push(Condition {
+                    is_kept: self.defines.contains_key(name) == expected,
+                    has_else: false,
+                }),
+                Directive::Else => {
+                    let condition = conditions
+                        .last_mut()
+                        .filter(|condition| !condition.has_else)
+                        .ok_or_else(|| unexpected("#else", &origin))?;
+
+                    condition.is_kept = !condition.is_kept;
+                    condition.has_else = true;
+                }
+                Directive::EndIf => {
+                    conditions
+                        .pop()
+                        .ok_or_else(|| unexpected("#endif", &origin))?;
+                }
+                _ if !is_kept => {}
+                Directive::Define(name, value) => {
+                    self.defines.insert(name.to_owned(), value.to_owned());
+                }
+                Directive::Undef(name) => {
+                    self.defines.remove(name);
+                }
+                Directive::Include(name) => self.include(name)?,
+            }
+        }
+
+        if !conditions.is_empty() {
+            return Err(Error::InvalidDirective(format!(
+                "Missing `#endif` in {}",
+                describe(&origin)
+            )));
+        }
+
+        self.declare(&kept, &origin)
+    }
+
+    /// Records the top-level names declared by the source, which must not be declared
+    /// by another module or by the frag including them.
+    fn declare(&mut self, source: &str, origin: &SourceOrigin) -> Result<(), Error> {
+        for name in declarations(source) {
+            match self.declarations.get(name) {
+                Some(declared) if declared != origin => {
+                    return Err(Error::InvalidInclude(format!(
+                        "`{}` is defined in both {} and {}",
+                        name,
+                        describe(declared),
+                        describe(origin)
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    self.declarations.insert(name.to_owned(), origin.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn include(&mut self, name: &'a str) -> Result<(), Error> {
+        if self.stack.contains(&name) {
+            let mut cycle = self.stack.clone();
+            cycle.push(name);
+
+            return Err(Error::InvalidInclude(format!(
+                "Includes form a cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        if !self.included.insert(name) {
+            return Ok(());
+        }
+
+        let module = self
+            .libraries
+            .iter()
+            .find_map(|library| library.get(name))
+            .ok_or_else(|| Error::InvalidInclude(format!("Unknown include `{}`", name)))?;
+
+        self.stack.push(name);
+        self.run(module, SourceOrigin::Include(name.to_owned()))?;
+        self.stack.pop();
+
+        // Keeps a trailing line comment of the module from swallowing the next line.
+        self.push(Piece::slice("\n", 0..1, SourceOrigin::BuiltIn));
+
+        Ok(())
+    }
+
+    /// Pushes the piece, merging it into the last one if it follows it in the same source.
+    fn push(&mut self, piece: Piece<'a>) {
+        if piece.text.is_empty() {
+            return;
+        }
+
+        if let Some(last) = self.pieces.last_mut() {
+            if let (Cow::Borrowed(_), Cow::Borrowed(text)) = (&last.text, &piece.text) {
+                if std::ptr::eq(last.source, piece.source)
+                    && last.origin == piece.origin
+                    && last.offset + last.text.len() == piece.offset
+                {
+                    last.text =
+                        Cow::Borrowed(&piece.source[last.offset..piece.offset + text.len()]);
+                    return;
+                }
+            }
+        }
+
+        self.pieces.push(piece);
+    }
+
+    /// Pushes the line of the source, replacing the names defined with a value
+    /// outside of the block comments the line starts in.
+    fn push_line(
+        &mut self,
+        source: &'a str,
+        range: Range<usize>,
+        depth: usize,
+        origin: &SourceOrigin,
+    ) {
+        let mut start = range.start;
+
+        for (token_range, token) in tokens_at_depth(&source[range.clone()], depth) {
+            let Some(value) = self.defines.get(token).filter(|value| !value.is_empty()) else {
+                continue;
+            };
+
+            let token_start = range.start + token_range.start;
+
+            let value = Piece {
+                text: Cow::Owned(value.clone()),
+                source,
+                offset: token_start,
+                origin: origin.clone(),
+            };
+
+            self.push(Piece::slice(source, start..token_start, origin.clone()));
+            self.push(value);
+
+            start = range.start + token_range.end;
+        }
+
+        self.push(Piece::slice(source, start..range.end, origin.clone()));
+    }
+}
+
+/// Returns the names declared at the top level of WGSL source.
+pub(crate) fn declarations(source: &str) -> Vec<&str> {
+    let tokens = tokens(source);
+    let mut names = vec![];
+    let mut depth = 0usize;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let token = tokens[index].1;
+        index += 1;
+
+        match token {
+            "{" => depth += 1,
+            "}" => depth = depth.saturating_sub(1),
+            "alias" | "const" | "fn" | "override" | "struct" | "var" if depth == 0 => {
+                // Skips the address space and the access mode, as in `var<storage, read>`.
+                if token == "var" && tokens.get(index).is_some_and(|(_, token)| *token == "<") {
+                    while let Some((_, token)) = tokens.get(index) {
+                        index += 1;
+
+                        if *token == ">" {
+                            break;
+                        }
+                    }
+                }
+
+                if let Some((_, name)) = tokens.get(index) {
+                    names.push(*name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
+
+fn describe(origin: &SourceOrigin) -> String {
+    match origin {
+        SourceOrigin::BuiltIn => "built-in code".to_owned(),
+        SourceOrigin::Frag => "the frag".to_owned(),
+        SourceOrigin::Include(name) => format!("include `{}`", name),
+        SourceOrigin::Pass(name) => format!("pass `{}`", name),
+    }
+}
+
+/// Returns the directive of the line, if any. A directive may be followed by a line comment.
+fn parse_directive<'s>(line: &'s str) -> Result<Option<Directive<'s>>, Error> {
+    let Some(directive) = line.trim_start().strip_prefix('#') else {
+        return Ok(None);
+    };
+
+    let directive = directive
+        .split_once("//")
+        .map_or(directive, |(directive, _)| directive)
+        .trim();
+
+    let (keyword, rest) = directive
+        .split_once(char::is_whitespace)
+        .map_or((directive, ""), |(keyword, rest)| (keyword, rest.trim()));
+
+    let malformed = || Error::InvalidDirective(format!("Malformed directive `#{}`", directive));
+    let name = |name: &'s str| Some(name).filter(|name| is_identifier(name));
+
+    Ok(Some(match keyword {
+        "define" => {
+            let (defined, value) = rest
+                .split_once(char::is_whitespace)
+                .map_or((rest, ""), |(name, value)| (name, value.trim()));
+
+            Directive::Define(name(defined).ok_or_else(malformed)?, value)
+        }
+        "else" if rest.is_empty() => Directive::Else,
+        "endif" if rest.is_empty() => Directive::EndIf,
+        "ifdef" => Directive::IfDef(name(rest).ok_or_else(malformed)?, true),
+        "ifndef" => Directive::IfDef(name(rest).ok_or_else(malformed)?, false),
+        "include" => Directive::Include(
+            rest.strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .filter(|name| !name.is_empty() && !name.contains('"'))
+                .ok_or_else(malformed)?,
+        ),
+        "undef" => Directive::Undef(name(rest).ok_or_else(malformed)?),
+        "else" | "endif" => return Err(malformed()),
+        _ => {
+            return Err(Error::InvalidDirective(format!(
+                "Unknown directive `#{}`",
+                keyword
+            )))
+        }
+    }))
+}
+
+fn unexpected(directive: &str, origin: &SourceOrigin) -> Error {
+    Error::InvalidDirective(format!(
+        "`{}` without `#ifdef` in {}",
+        directive,
+        describe(origin)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> Result<String, Error> {
+        run_with(source, &[])
+    }
+
+    /// Preprocesses the source with the modules given by name and source.
+    fn run_with(source: &str, modules: &[(&str, &str)]) -> Result<String, Error> {
+        let mut library = Library::new();
+        for (name, module) in modules {
+            library.insert(name, module);
+        }
+
+        let pieces = preprocess(source, SourceOrigin::Frag, &[&library], HashMap::new())?;
+
+        Ok(pieces.iter().map(|piece| piece.text.as_ref()).collect())
+    }
+
+    /// Asserts that the source fails to include the modules with the message.
+    fn assert_include_fails(source: &str, modules: &[(&str, &str)], message: &str) {
+        match run_with(source, modules) {
+            Err(Error::InvalidInclude(err)) => assert!(err.contains(message), "{}", err),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn directive_lines_in_block_comments_are_kept() {
+        let source = "/*\n# not a directive\n/* nested */\n#endif\n*/\nfn main_image() {}\n";
+
+        assert_eq!(run(source).unwrap(), source);
+    }
+
+    #[test]
+    fn directives_after_block_comments_apply() {
+        let source = "/* a */ /*\n*/\n#define A 1.0 // one\n// #define A 2.0\nlet a = A;\n";
+
+        assert_eq!(
+            run(source).unwrap(),
+            "/* a */ /*\n*/\n// #define A 2.0\nlet a = 1.0;\n"
+        );
+    }
+
+    #[test]
+    fn nested_block_comments_hide_directives_and_names() {
+        let source = "#define A 1.0\n/* /* A */\n#define A 2.0\nA */\nlet a = A;\n";
+
+        assert_eq!(
+            run(source).unwrap(),
+            "/* /* A */\n#define A 2.0\nA */\nlet a = 1.0;\n"
+        );
+    }
+
+    #[test]
+    fn unknown_directives_fail() {
+        assert!(matches!(run("#not\n"), Err(Error::InvalidDirective(_))));
+    }
+
+    #[test]
+    fn include_cycles_fail() {
+        let modules = [("a", "#include \"b\"\n"), ("b", "#include \"a\"\n")];
+
+        assert_include_fails("#include \"a\"\n", &modules, "a -> b -> a");
+        assert_include_fails("#include \"c\"\n", &[("c", "#include \"c\"\n")], "c -> c");
+    }
+
+    #[test]
+    fn modules_are_included_once() {
+        let modules = [("a", "fn a() {}\n"), ("b", "#include \"a\"\nfn b() {}\n")];
+        let shader = run_with("#include \"a\"\n#include \"b\"\n", &modules).unwrap();
+
+        assert_eq!(shader.matches("fn a()").count(), 1);
+        assert!(shader.contains("fn b()"));
+    }
+
+    #[test]
+    fn names_declared_twice_fail() {
+        let modules = [("a", "fn f() {}\n"), ("b", "const f = 1;\n")];
+
+        assert_include_fails(
+            "#include \"a\"\n#include \"b\"\n",
+            &modules,
+            "`f` is defined in both include `a` and include `b`",
+        );
+        assert_include_fails("#include \"a\"\nfn f() {}\n", &modules, "`f`");
+    }
+
+    #[test]
+    fn unknown_includes_fail() {
+        assert_include_fails("#include \"a\"\n", &[], "Unknown include `a`");
+    }
+
+    #[test]
+    fn defines_carry_on_from_modules() {
+        let modules = [("a", "#define GAIN 2.0\n")];
+        let shader = run_with(
+            "#include \"a\"\nlet a = GAIN;\n#define GAIN 3.0\nlet b = GAIN;\n",
+            &modules,
+        )
+        .unwrap();
+
+        assert_eq!(shader, "\nlet a = 2.0;\nlet b = 3.0;\n");
+    }
+}
@@ -0,0 +1,269 @@
+//! Finds out what a complete fragment shader reads, with the cargo feature `validate`.
+
+use crate::{
+    preprocess::declarations,
+    validate::{parse_shader, Diagnostic},
+    SourceMap, SourceOrigin, FRAG_PREFIX,
+};
+use naga::{Expression, Handle, Statement};
+use std::collections::HashSet;
+
+/// What a complete fragment shader reads from `main_image`, directly or through
+/// the functions it calls. Returned by [`reflect_shader`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reflection {
+    /// The fields of the uniforms `u` read, e.g. `time`, in the order of their declaration.
+    pub uniforms: Vec<String>,
+    /// The fields of the parameters `p` read, in the order of their declaration.
+    pub params: Vec<String>,
+    /// The textures read, e.g. `texture0`, `previous_frame` or the output of a buffer pass,
+    /// in the order of their groups and bindings.
+    pub textures: Vec<String>,
+    /// The samplers read, e.g. `sampler0`, in the order of their groups and bindings.
+    pub samplers: Vec<String>,
+    /// The built-in functions called, e.g. `image`.
+    pub builtins: Vec<String>,
+}
+
+impl Reflection {
+    /// Whether the texture declared as the given name, e.g. `texture0`, is read.
+    pub fn reads_texture(&self, name: &str) -> bool {
+        self.textures.iter().any(|texture| texture == name)
+    }
+}
+
+/// Parses a complete WGSL fragment shader, e.g. one generated by
+/// [`WgsData::shader_frag`](crate::WgsData::shader_frag), and finds out what its
+/// `main_image` reads.
+///
+/// Returns the diagnostics if the shader fails to compile or has no `main_image`.
+/// The whole shader is regarded as [`SourceOrigin::Frag`].
+pub fn reflect_shader(shader_frag: &str) -> Result<Reflection, Vec<Diagnostic>> {
+    let mut source_map = SourceMap::default();
+    source_map.push(0..shader_frag.len(), SourceOrigin::Frag, 0, shader_frag);
+
+    reflect_shader_with_source_map(shader_frag, &source_map)
+}
+
+/// Finds out what the `main_image` of a complete WGSL fragment shader reads like
+/// [`reflect_shader`], mapping the spans of the diagnostics with the [`SourceMap`].
+pub fn reflect_shader_with_source_map(
+    shader_frag: &str,
+    source_map: &SourceMap,
+) -> Result<Reflection, Vec<Diagnostic>> {
+    let (module, _) = parse_shader(shader_frag, source_map)?;
+
+    let main_image = module
+        .functions
+        .iter()
+        .find(|(_, function)| function.name.as_deref() == Some("main_image"))
+        .map(|(handle, _)| handle)
+        .ok_or_else(|| vec![Diagnostic::new("No `main_image` function found")])?;
+
+    let mut functions = HashSet::new();
+    let mut pending = vec![main_image];
+    while let Some(handle) = pending.pop() {
+        if functions.insert(handle) {
+            collect_calls(&module.functions[handle].body, &mut pending);
+        }
+    }
+
+    // The globals read as a whole, and the fields of the globals read on their own.
+    let mut globals = HashSet::new();
+    let mut fields = HashSet::new();
+
+    for &handle in &functions {
+        let expressions = &module.functions[handle].expressions;
+
+        let mut bases = HashSet::new();
+        for (_, expression) in expressions.iter() {
+            if let Expression::AccessIndex { base, index } = *expression {
+                if let Expression::GlobalVariable(global) = expressions[base] {
+                    fields.insert((global, index));
+                    bases.insert(base);
+                }
+            }
+        }
+
+        for (handle, expression) in expressions.iter() {
+            if let Expression::GlobalVariable(global) = *expression {
+                if !bases.contains(&handle) {
+                    globals.insert(global);
+                }
+            }
+        }
+    }
+
+    let is_read = |global: Handle<naga::GlobalVariable>| {
+        globals.contains(&global) || fields.iter().any(|(field, _)| *field == global)
+    };
+
+    let read_members = |global: Handle<naga::GlobalVariable>| -> Vec<String> {
+        let naga::TypeInner::Struct { members, .. } =
+            &module.types[module.global_variables[global].ty].inner
+        else {
+            return vec![];
+        };
+
+        members
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                globals.contains(&global) || fields.contains(&(global, *index as u32))
+            })
+            .filter_map(|(_, member)| member.name.clone())
+            .collect()
+    };
+
+    let mut reflection = Reflection::default();
+
+    let mut resources: Vec<_> = module
+        .global_variables
+        .iter()
+        .filter_map(|(handle, global)| Some((global.binding.clone()?, handle, global)))
+        .collect();
+    resources.sort_by_key(|(binding, _, _)| (binding.group, binding.binding));
+
+    for (_, handle, global) in resources {
+        let name = global.name.clone().unwrap_or_default();
+
+        match module.types[global.ty].inner {
+            _ if name == "u" => reflection.uniforms = read_members(handle),
+            _ if name == "p" => reflection.params = read_members(handle),
+            naga::TypeInner::Image { .. } if is_read(handle) => reflection.textures.push(name),
+            naga::TypeInner::Sampler { .. } if is_read(handle) => reflection.samplers.push(name),
+            _ => {}
+        }
+    }
+
+    let builtins = declarations(FRAG_PREFIX);
+
+    reflection.builtins = module
+        .functions
+        .iter()
+        .filter(|(handle, _)| functions.contains(handle))
+        .filter_map(|(_, function)| function.name.clone())
+        .filter(|name| builtins.contains(&name.as_str()))
+        .collect();
+
+    Ok(reflection)
+}
+
+/// Collects the functions called in the block.
+fn collect_calls(block: &naga::Block, calls: &mut Vec<Handle<naga::Function>>) {
+    for statement in block.iter() {
+        match statement {
+            Statement::Block(block) => collect_calls(block, calls),
+            Statement::If { accept, reject, .. } => {
+                collect_calls(accept, calls);
+                collect_calls(reject, calls);
+            }
+            Statement::Loop {
+                body, continuing, ..
+            } => {
+                collect_calls(body, calls);
+                collect_calls(continuing, calls);
+            }
+            Statement::Switch { cases, .. } => {
+                for case in cases {
+                    collect_calls(&case.body, calls);
+                }
+            }
+            Statement::Call { function, .. } => calls.push(*function),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Param, ParamKind, Pass, PassInput, WgsData};
+
+    /// Reflects the image of wgs data of the frag with two textures.
+    fn reflect(frag: &str) -> Reflection {
+        let mut wgs = WgsData::new("reflect", frag);
+        wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+        wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+        wgs.add_param(Param::new("on", ParamKind::Bool { default: true }));
+        wgs.add_param(Param::new("gain", ParamKind::Bool { default: true }));
+
+        let (shader, source_map) = wgs.shader_frag().unwrap();
+        reflect_shader_with_source_map(&shader, &source_map).unwrap()
+    }
+
+    #[test]
+    fn reads_through_called_functions_are_found() {
+        let reflection = reflect(
+            "fn sampled(uv: vec2<f32>) -> vec4<f32> {
+                return image(texture1, sampler1, uv) * u.time;
+            }
+
+            fn unused() -> vec2<f32> {
+                return u.cursor + vec2(f32(p.on));
+            }
+
+            fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {
+                return sampled(p / u.resolution);
+            }",
+        );
+
+        assert_eq!(
+            reflection,
+            Reflection {
+                uniforms: vec!["resolution".to_owned(), "time".to_owned()],
+                params: vec![],
+                textures: vec!["texture1".to_owned()],
+                samplers: vec!["sampler1".to_owned()],
+                builtins: vec!["image".to_owned()],
+            }
+        );
+        assert!(reflection.reads_texture("texture1"));
+        assert!(!reflection.reads_texture("texture0"));
+    }
+
+    #[test]
+    fn struct_read_as_a_whole_reads_every_field() {
+        let reflection = reflect(
+            "fn main_image(c: vec4<f32>, coord: vec2<f32>) -> vec4<f32> {
+                let params = p;
+                return vec4(f32(params.gain));
+            }",
+        );
+
+        assert_eq!(reflection.params, ["on", "gain"]);
+        assert!(reflection.uniforms.is_empty());
+    }
+
+    #[test]
+    fn missing_main_image_fails() {
+        let diagnostics = reflect_shader(crate::FRAG_PREFIX).unwrap_err();
+
+        assert!(diagnostics[0].message.contains("main_image"));
+    }
+
+    #[test]
+    fn textures_read_by_no_shader_are_unused() {
+        let mut wgs = WgsData::new(
+            "unused",
+            "fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {
+                return image(noise, noise_sampler, p);
+            }",
+        );
+        for _ in 0..3 {
+            wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+        }
+        wgs.set_texture_name(0, Some("noise")).unwrap();
+
+        let mut pass = Pass::new(
+            "buf",
+            "fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {
+                return image(texture2, sampler2, p);
+            }",
+        );
+        pass.inputs = vec![PassInput::Texture(2)];
+        wgs.add_pass(pass);
+
+        assert_eq!(wgs.unused_textures().unwrap(), [1]);
+    }
+}
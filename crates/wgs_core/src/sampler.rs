@@ -0,0 +1,140 @@
+/// How a texture is sampled by the shader.
+///
+/// The default matches the behavior before samplers were stored in wgs files:
+/// clamp-to-edge addressing, nearest filtering and no mipmaps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TextureSampler {
+    /// How the texture coordinates out of `[0, 1]` are handled.
+    pub address_mode: AddressMode,
+    /// How the texture is filtered when magnified.
+    pub mag_filter: FilterMode,
+    /// How the texture is filtered when minified.
+    pub min_filter: FilterMode,
+    /// How the texture is filtered between mipmap levels.
+    /// `None` means the texture has no mipmaps.
+    pub mipmap_filter: Option<FilterMode>,
+}
+
+/// How the texture coordinates out of `[0, 1]` are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum AddressMode {
+    /// Uses the color of the nearest edge.
+    #[default]
+    ClampToEdge,
+    /// Tiles the texture.
+    Repeat,
+    /// Tiles the texture, mirroring every other tile.
+    MirrorRepeat,
+}
+
+/// How the texels are combined when a texture is sampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum FilterMode {
+    /// Uses the nearest texel. Keeps pixel art crisp.
+    #[default]
+    Nearest,
+    /// Interpolates the nearest texels linearly.
+    Linear,
+}
+
+impl TextureSampler {
+    /// Decodes the sampler from its stored form. Unknown values fall back to the defaults.
+    pub(crate) fn from_bytes(bytes: [u8; 4]) -> Self {
+        let filter = |value| match value {
+            1 => FilterMode::Linear,
+            _ => FilterMode::Nearest,
+        };
+
+        Self {
+            address_mode: match bytes[0] {
+                1 => AddressMode::Repeat,
+                2 => AddressMode::MirrorRepeat,
+                _ => AddressMode::ClampToEdge,
+            },
+            mag_filter: filter(bytes[1]),
+            min_filter: filter(bytes[2]),
+            mipmap_filter: match bytes[3] {
+                0 => None,
+                value => Some(filter(value - 1)),
+            },
+        }
+    }
+
+    pub(crate) fn to_bytes(self) -> [u8; 4] {
+        let filter = |filter| match filter {
+            FilterMode::Nearest => 0,
+            FilterMode::Linear => 1,
+        };
+
+        [
+            match self.address_mode {
+                AddressMode::ClampToEdge => 0,
+                AddressMode::Repeat => 1,
+                AddressMode::MirrorRepeat => 2,
+            },
+            filter(self.mag_filter),
+            filter(self.min_filter),
+            self.mipmap_filter
+                .map_or(0, |mipmap_filter| filter(mipmap_filter) + 1),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sampler_round_trips() {
+        let filters = [FilterMode::Nearest, FilterMode::Linear];
+
+        for address_mode in [
+            AddressMode::ClampToEdge,
+            AddressMode::Repeat,
+            AddressMode::MirrorRepeat,
+        ] {
+            for (mag_filter, min_filter) in filters
+                .into_iter()
+                .flat_map(|mag_filter| filters.map(|min_filter| (mag_filter, min_filter)))
+            {
+                for mipmap_filter in [None, Some(FilterMode::Nearest), Some(FilterMode::Linear)] {
+                    let sampler = TextureSampler {
+                        address_mode,
+                        mag_filter,
+                        min_filter,
+                        mipmap_filter,
+                    };
+
+                    assert_eq!(TextureSampler::from_bytes(sampler.to_bytes()), sampler);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bytes_are_laid_out_as_documented() {
+        let sampler = TextureSampler {
+            address_mode: AddressMode::MirrorRepeat,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: Some(FilterMode::Linear),
+        };
+
+        assert_eq!(sampler.to_bytes(), [2, 1, 0, 2]);
+        assert_eq!(TextureSampler::default().to_bytes(), [0; 4]);
+    }
+
+    #[test]
+    fn unknown_bytes_fall_back_to_the_defaults() {
+        let sampler = TextureSampler::from_bytes([9, 9, 9, 9]);
+
+        assert_eq!(sampler.address_mode, AddressMode::ClampToEdge);
+        assert_eq!(sampler.mag_filter, FilterMode::Nearest);
+        assert_eq!(sampler.min_filter, FilterMode::Nearest);
+        assert_eq!(sampler.mipmap_filter, Some(FilterMode::Nearest));
+    }
+}
@@ -0,0 +1,128 @@
+//! The text representation of wgs data, with the cargo feature `serde`.
+//!
+//! The binary parts, like the pixel data of textures, are stored as base64.
+
+use crate::{codec, sampler::TextureSampler, texture::TextureEncoding, Texture};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Bytes as a base64 string.
+pub(crate) mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        STANDARD
+            .decode(String::deserialize(deserializer)?)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// A [`NullString`](binrw::NullString) as a plain string.
+pub(crate) mod null_string {
+    use super::*;
+    use binrw::NullString;
+
+    pub fn serialize<S: Serializer>(string: &NullString, serializer: S) -> Result<S::Ok, S::Error> {
+        let string = std::str::from_utf8(string).map_err(serde::ser::Error::custom)?;
+
+        serializer.serialize_str(string)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NullString, D::Error> {
+        Ok(String::deserialize(deserializer)?.as_str().into())
+    }
+}
+
+/// A texture with its pixel data in its encoding, so that compressed textures stay small.
+#[derive(Deserialize, Serialize)]
+struct TextureDocument {
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    encoding: TextureEncoding,
+    #[serde(default)]
+    sampler: TextureSampler,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(with = "bytes")]
+    data: Vec<u8>,
+}
+
+impl Serialize for Texture {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = codec::encode(self.encoding, self.width, self.height, &self.data)
+            .map_err(serde::ser::Error::custom)?;
+
+        TextureDocument {
+            width: self.width,
+            height: self.height,
+            encoding: self.encoding,
+            sampler: self.sampler,
+            name: self.name.clone(),
+            data,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Texture {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let document = TextureDocument::deserialize(deserializer)?;
+
+        let data = codec::decode(
+            document.encoding,
+            document.width,
+            document.height,
+            document.data,
+        )
+        .map_err(de::Error::custom)?;
+
+        let mut texture =
+            Texture::new(document.width, document.height, data).map_err(de::Error::custom)?;
+        texture.encoding = document.encoding;
+        texture.sampler = document.sampler;
+        texture.name = document.name;
+
+        Ok(texture)
+    }
+}
+
+#[cfg(all(test, feature = "dir"))]
+mod tests {
+    use crate::{Texture, TextureEncoding, WgsData};
+
+    #[test]
+    fn texture_round_trips_in_its_encoding() {
+        let mut texture = Texture::new(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        texture.encoding = TextureEncoding::Png;
+        texture.name = Some("noise".to_owned());
+
+        let mut wgs = WgsData::new("serde", "fn main_image() {}");
+        wgs.add_texture(2, 1, texture.data.clone()).unwrap();
+        wgs.set_texture(0, texture).unwrap();
+
+        let text = toml::to_string(&wgs).unwrap();
+        let loaded: WgsData = toml::from_str(&text).unwrap();
+
+        let texture = loaded.texture(0).unwrap();
+        assert_eq!(texture.data, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(texture.encoding, TextureEncoding::Png);
+        assert_eq!(texture.name.as_deref(), Some("noise"));
+    }
+
+    #[test]
+    fn texture_of_another_size_fails() {
+        let mut wgs = WgsData::new("serde", "fn main_image() {}");
+        wgs.add_texture(1, 1, vec![1, 2, 3, 4]).unwrap();
+        let text = toml::to_string(&wgs).unwrap();
+        assert!(text.contains("width = 1\n"), "{}", text);
+
+        let err =
+            toml::from_str::<WgsData>(&text.replace("width = 1\n", "width = 2\n")).unwrap_err();
+        assert!(err.to_string().contains("expects"), "{}", err);
+    }
+}
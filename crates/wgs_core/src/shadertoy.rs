@@ -0,0 +1,315 @@
+//! Imports Shadertoy shaders, with the cargo feature `shadertoy`.
+//!
+//! The GLSL source is wrapped into a complete shader, whose uniforms and textures are laid out
+//! like the ones of wgs, compiled by naga and written back as WGSL. The declarations which are
+//! provided by wgs are then removed, and a `main_image` calling `mainImage` is appended.
+
+use crate::{
+    token::identifiers,
+    validate::{validate_module, Diagnostic, Label},
+    ShaderBuilder, SourceOrigin, WgsData,
+};
+
+const PREFIX: &str = include_str!("./assets/shadertoy.prefix.glsl");
+const SUFFIX: &str = include_str!("./assets/shadertoy.suffix.glsl");
+
+const MAIN_IMAGE: &str = "
+fn main_image(frag_color: vec4<f32>, frag_coord: vec2<f32>) -> vec4<f32> {
+    var color = frag_color;
+    mainImage(&color, frag_coord);
+    return color;
+}
+";
+
+/// The number of `iChannel`s of Shadertoy.
+const CHANNEL_COUNT: usize = 4;
+
+/// The helper functions of [`PREFIX`], which are kept only if they are used.
+const HELPERS: [&str; 4] = [
+    "wgs_texel_fetch",
+    "wgs_texture",
+    "wgs_texture_lod",
+    "wgs_texture_size",
+];
+
+/// The inputs of Shadertoy which have no counterpart in wgs.
+const UNSUPPORTED_INPUTS: [&str; 7] = [
+    "iChannelResolution",
+    "iChannelTime",
+    "iDate",
+    "iFrame",
+    "iFrameRate",
+    "iSampleRate",
+    "iTimeDelta",
+];
+
+/// Imports a Shadertoy shader, which defines `void mainImage(out vec4 fragColor, in vec2 fragCoord)`
+/// in GLSL, as wgs data of the given name.
+///
+/// The inputs of Shadertoy are mapped as follows:
+///
+/// - `iResolution` to `vec3(u.resolution, 1.0)`.
+/// - `iTime` to `u.time`.
+/// - `iMouse` to `u.cursor` and `u.mouse_press`, with the sign of `z` telling whether the left
+///   button is down. Unlike Shadertoy, `xy` keeps following the cursor when the button is up.
+/// - `iChannel0` to `iChannel3` to `texture0` to `texture3` and their samplers, sampled like
+///   the built-in function `image` does. Each channel used is backed by a black texture of 1x1,
+///   to be replaced with [`WgsData::change_texture`].
+///
+/// # Errors
+///
+/// Returns the diagnostics if the shader uses an input wgs doesn't provide, like `iFrame`,
+/// or a construct naga can't translate, like a `sampler2D` function parameter,
+/// or if the translated shader clashes with the built-in code of wgs, like a function named `image`.
+/// The spans of the diagnostics refer to the GLSL source as [`SourceOrigin::Frag`].
+pub fn import_shadertoy(name: &str, source: &str) -> Result<WgsData, Vec<Diagnostic>> {
+    let mut builder = ShaderBuilder::default();
+    builder.push(PREFIX, SourceOrigin::BuiltIn);
+    builder.push(source, SourceOrigin::Frag);
+    builder.push(SUFFIX, SourceOrigin::BuiltIn);
+
+    let (glsl, source_map) = builder.finish();
+
+    let mut diagnostics = vec![];
+    let mut channel_count = 0;
+
+    for (range, identifier) in identifiers(source) {
+        if UNSUPPORTED_INPUTS.contains(&identifier) {
+            let span = naga::Span::from(PREFIX.len() + range.start..PREFIX.len() + range.end);
+
+            diagnostics.push(Diagnostic {
+                message: format!(
+                    "The Shadertoy input `{}` is not supported by wgs",
+                    identifier
+                ),
+                labels: Label::new(&glsl, &source_map, span, "")
+                    .into_iter()
+                    .collect(),
+                notes: vec![],
+            });
+        }
+
+        if let Some(index) = identifier
+            .strip_prefix("iChannel")
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|index| *index < CHANNEL_COUNT)
+        {
+            channel_count = channel_count.max(index + 1);
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    let mut module = naga::front::glsl::Frontend::default()
+        .parse(
+            &naga::front::glsl::Options::from(naga::ShaderStage::Fragment),
+            &glsl,
+        )
+        .map_err(|err| {
+            err.errors
+                .into_iter()
+                .map(|err| Diagnostic {
+                    message: err.kind.to_string(),
+                    labels: Label::new(&glsl, &source_map, err.meta, "")
+                        .into_iter()
+                        .collect(),
+                    notes: vec![],
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+    if !module
+        .functions
+        .iter()
+        .any(|(_, function)| function.name.as_deref() == Some("mainImage"))
+    {
+        return Err(vec![Diagnostic::new(
+            "No `mainImage(out vec4, in vec2)` function found",
+        )]);
+    }
+
+    // The empty `main` of the suffix is only there to satisfy the frontend.
+    module.entry_points.clear();
+
+    let info = validate_module(&module, &glsl, &source_map)?;
+
+    let wgsl =
+        naga::back::wgsl::write_string(&module, &info, naga::back::wgsl::WriterFlags::empty())
+            .map_err(|err| vec![Diagnostic::new(&err.to_string())])?;
+
+    let mut frag = translate_output(&wgsl).trim_end().to_owned();
+    frag.push('\n');
+    frag.push_str(MAIN_IMAGE);
+
+    let mut wgs = WgsData::new(name, &frag);
+
+    for _ in 0..channel_count {
+        wgs.add_texture(1, 1, vec![0, 0, 0, u8::MAX])
+            .map_err(|err| vec![Diagnostic::new(&err.to_string())])?;
+    }
+
+    wgs.validate()?;
+
+    Ok(wgs)
+}
+
+/// Turns the WGSL written by naga into a frag of wgs.
+fn translate_output(wgsl: &str) -> String {
+    // The names ending in a digit are suffixed with `_` by naga.
+    let channels: Vec<(String, String)> = (0..CHANNEL_COUNT)
+        .flat_map(|index| {
+            [
+                (format!("texture{}_", index), format!("texture{}", index)),
+                (format!("sampler{}_", index), format!("sampler{}", index)),
+            ]
+        })
+        .collect();
+
+    let wgsl = remove_items(wgsl, |name| {
+        name == "Uniforms"
+            || name == "u"
+            || name == "main"
+            || channels.iter().any(|(from, _)| from == name)
+    });
+
+    let used: Vec<&str> = identifiers(&wgsl)
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+
+    let wgsl = remove_items(&wgsl, |name| {
+        HELPERS.contains(&name) && used.iter().filter(|used| **used == name).count() == 1
+    });
+
+    let mut frag = String::new();
+    let mut end = 0;
+
+    for (range, identifier) in identifiers(&wgsl) {
+        if let Some((_, to)) = channels.iter().find(|(from, _)| from == identifier) {
+            frag.push_str(&wgsl[end..range.start]);
+            frag.push_str(to);
+            end = range.end;
+        }
+    }
+
+    frag.push_str(&wgsl[end..]);
+
+    frag
+}
+
+/// Removes the top-level declarations whose names match from WGSL written by naga,
+/// which puts each declaration at the start of a line and closes each block with `}` alone.
+fn remove_items(wgsl: &str, remove: impl Fn(&str) -> bool) -> String {
+    let mut output = String::new();
+    let mut attributes = String::new();
+    let mut lines = wgsl.lines();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with('@') {
+            attributes.push_str(line);
+            attributes.push('\n');
+            continue;
+        }
+
+        let mut item = std::mem::take(&mut attributes);
+        item.push_str(line);
+        item.push('\n');
+
+        if line.ends_with('{') {
+            for line in lines.by_ref() {
+                item.push_str(line);
+                item.push('\n');
+
+                if line == "}" {
+                    break;
+                }
+            }
+        }
+
+        let name = line
+            .split_once(' ')
+            .and_then(|(_, rest)| identifiers(rest).into_iter().next())
+            .map(|(_, name)| name);
+
+        if name.is_some_and(&remove) {
+            continue;
+        }
+
+        if item == "\n" && (output.is_empty() || output.ends_with("\n\n")) {
+            continue;
+        }
+
+        output.push_str(&item);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRADIENT: &str = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    fragColor = vec4(uv, 0.5 + 0.5 * sin(iTime), 1.0);
+}
+";
+
+    #[test]
+    fn shader_is_imported() {
+        let wgs = import_shadertoy("gradient", GRADIENT).unwrap();
+
+        assert_eq!(wgs.name(), "gradient");
+        assert!(wgs.frag().contains("fn main_image("));
+        assert_eq!(wgs.textures().count(), 0);
+        assert!(wgs.validate().is_ok());
+    }
+
+    #[test]
+    fn each_channel_up_to_the_last_used_gets_a_texture() {
+        let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    fragColor = texture(iChannel1, fragCoord / iResolution.xy);
+}
+";
+        let wgs = import_shadertoy("channels", source).unwrap();
+
+        assert_eq!(wgs.textures().count(), 2);
+        assert!(wgs.frag().contains("texture1"));
+    }
+
+    #[test]
+    fn unsupported_inputs_point_into_the_source() {
+        let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    fragColor = vec4(float(iFrame));
+}
+";
+        let diagnostics = import_shadertoy("frame", source).unwrap_err();
+        let label = &diagnostics[0].labels[0];
+
+        assert!(diagnostics[0].message.contains("`iFrame`"));
+        assert_eq!(label.origin, SourceOrigin::Frag);
+        assert_eq!((label.line, label.column), (2, 28));
+    }
+
+    #[test]
+    fn missing_main_image_fails() {
+        let diagnostics = import_shadertoy("empty", "float f() { return 1.0; }\n").unwrap_err();
+
+        assert!(diagnostics[0].message.contains("mainImage"));
+    }
+
+    #[test]
+    fn glsl_errors_point_into_the_source() {
+        let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    fragColor = vec4(unknown);
+}
+";
+        let diagnostics = import_shadertoy("broken", source).unwrap_err();
+        let label = &diagnostics[0].labels[0];
+
+        assert_eq!(label.origin, SourceOrigin::Frag);
+        assert_eq!(label.line, 2);
+    }
+}
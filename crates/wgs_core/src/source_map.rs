@@ -0,0 +1,248 @@
+use std::ops::Range;
+
+/// Where a piece of a generated shader comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SourceOrigin {
+    /// Code generated or provided by wgs itself, e.g. the uniforms, the built-in functions
+    /// and the texture declarations. Not editable by users.
+    BuiltIn,
+    /// The editable part of the fragment shader. See [`WgsData::frag`](crate::WgsData::frag).
+    Frag,
+    /// The module of the given name pulled in by an `#include` directive.
+    /// See [`Library`](crate::Library).
+    Include(String),
+    /// The editable part of the fragment shader of the buffer pass of the given name.
+    /// See [`Pass::frag`](crate::Pass::frag).
+    Pass(String),
+}
+
+/// A position in the source a piece of a generated shader comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The source the position belongs to.
+    pub origin: SourceOrigin,
+    /// The byte range in the source.
+    ///
+    /// For [`SourceOrigin::BuiltIn`], it's the byte range in the generated shader.
+    pub span: Range<usize>,
+    /// The line where the range starts, starting from 1.
+    pub line: u32,
+    /// The column in bytes where the range starts, starting from 1.
+    pub column: u32,
+}
+
+/// Maps the byte ranges of a generated shader back to the sources it's made of.
+///
+/// Produced alongside the shader by [`concat_shader_frag_with_source_map`](crate::concat_shader_frag_with_source_map).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Segment {
+    /// The byte range in the generated shader.
+    range: Range<usize>,
+    origin: SourceOrigin,
+    /// The byte offset in the origin source where the segment starts.
+    offset: usize,
+    /// The line in the origin source where the segment starts.
+    line: u32,
+    /// The column in the origin source where the segment starts.
+    column: u32,
+}
+
+impl SourceMap {
+    /// Locates the byte range of the generated shader in the source it comes from.
+    ///
+    /// Ranges not covered by the map, or spreading over more than one source,
+    /// are reported as [`SourceOrigin::BuiltIn`].
+    pub fn locate(&self, generated: &str, span: Range<usize>) -> SourceLocation {
+        let segment = self
+            .segments
+            .iter()
+            .find(|segment| segment.range.contains(&span.start) && span.end <= segment.range.end);
+
+        match segment {
+            Some(segment) => {
+                let start = span.start - segment.range.start;
+                let (line, column) = advance(
+                    segment.line,
+                    segment.column,
+                    &generated[segment.range.start..span.start],
+                );
+
+                SourceLocation {
+                    origin: segment.origin.clone(),
+                    span: segment.offset + start..segment.offset + start + span.len(),
+                    line,
+                    column,
+                }
+            }
+            None => {
+                let (line, column) = advance(1, 1, &generated[..span.start.min(generated.len())]);
+
+                SourceLocation {
+                    origin: SourceOrigin::BuiltIn,
+                    span,
+                    line,
+                    column,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        range: Range<usize>,
+        origin: SourceOrigin,
+        offset: usize,
+        source: &str,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+
+        let (line, column) = advance(1, 1, &source[..offset]);
+
+        self.segments.push(Segment {
+            range,
+            origin,
+            offset,
+            line,
+            column,
+        });
+    }
+}
+
+/// Builds a shader piece by piece while recording where each piece comes from.
+#[derive(Default)]
+pub(crate) struct ShaderBuilder {
+    shader: String,
+    source_map: SourceMap,
+}
+
+impl ShaderBuilder {
+    pub fn finish(self) -> (String, SourceMap) {
+        (self.shader, self.source_map)
+    }
+
+    pub fn push(&mut self, source: &str, origin: SourceOrigin) {
+        self.push_slice(source, 0..source.len(), origin);
+    }
+
+    /// Pushes the text, which maps back to the byte offset in the source.
+    pub fn push_mapped(&mut self, text: &str, source: &str, offset: usize, origin: SourceOrigin) {
+        let start = self.shader.len();
+        self.shader.push_str(text);
+
+        if origin != SourceOrigin::BuiltIn {
+            self.source_map
+                .push(start..self.shader.len(), origin, offset, source);
+        }
+    }
+
+    /// Pushes part of the source, keeping the offsets relative to the whole source.
+    pub fn push_slice(&mut self, source: &str, range: Range<usize>, origin: SourceOrigin) {
+        self.push_mapped(&source[range.clone()], source, range.start, origin);
+    }
+}
+
+fn advance(mut line: u32, mut column: u32, text: &str) -> (u32, u32) {
+    for byte in text.bytes() {
+        if byte == b'\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Library, WgsData};
+
+    const FRAG: &str = "// image
+#include \"noise\"
+#define GAIN 2.5
+fn main_image(c: vec4<f32>, p: vec2<f32>) -> vec4<f32> {
+    return c * GAIN * noise(p);
+}
+";
+
+    const NOISE: &str = "// noise
+fn noise(p: vec2<f32>) -> f32 {
+    return fract(p.x);
+}
+";
+
+    /// Generates the shader of the frag including the noise.
+    fn shader() -> (String, SourceMap) {
+        let mut library = Library::new();
+        library.insert("noise", NOISE);
+
+        let mut wgs = WgsData::new("source map", FRAG);
+        wgs.set_library(library);
+
+        wgs.shader_frag().unwrap()
+    }
+
+    /// Locates the first occurrence of the text in the shader.
+    fn locate(text: &str) -> SourceLocation {
+        let (shader, source_map) = shader();
+        let start = shader.find(text).unwrap();
+
+        source_map.locate(&shader, start..start + text.len())
+    }
+
+    #[test]
+    fn frag_is_located_in_the_frag() {
+        let location = locate("fn main_image");
+        let start = FRAG.find("fn main_image").unwrap();
+
+        assert_eq!(location.origin, SourceOrigin::Frag);
+        assert_eq!(location.span, start..start + "fn main_image".len());
+        assert_eq!((location.line, location.column), (4, 1));
+    }
+
+    #[test]
+    fn include_is_located_in_its_module() {
+        let location = locate("fract");
+        let start = NOISE.find("fract").unwrap();
+
+        assert_eq!(location.origin, SourceOrigin::Include("noise".to_owned()));
+        assert_eq!(location.span, start..start + "fract".len());
+        assert_eq!((location.line, location.column), (3, 12));
+    }
+
+    #[test]
+    fn defined_value_is_located_at_its_name() {
+        let location = locate("2.5");
+
+        assert_eq!(location.origin, SourceOrigin::Frag);
+        assert_eq!((location.line, location.column), (5, 16));
+    }
+
+    #[test]
+    fn prefix_is_built_in() {
+        let location = locate("struct Uniforms");
+
+        assert_eq!(location.origin, SourceOrigin::BuiltIn);
+        assert_eq!((location.line, location.column), (1, 1));
+    }
+
+    #[test]
+    fn range_over_two_sources_is_built_in() {
+        let (shader, source_map) = shader();
+        let span = shader.find("return c").unwrap()..shader.find("@fragment").unwrap();
+
+        assert_eq!(
+            source_map.locate(&shader, span).origin,
+            SourceOrigin::BuiltIn
+        );
+    }
+}
@@ -1,3 +1,10 @@
+use crate::{
+    chunk::read_bytes,
+    codec::texture_len,
+    pass::{is_identifier, reserved_names},
+    sampler::TextureSampler,
+    Error, Pass,
+};
 use binrw::binrw;
 use std::fmt;
 
@@ -9,9 +16,59 @@ pub struct Texture {
     pub width: u32,
     /// The height of the texture.
     pub height: u32,
-    #[br(count = width * height * 4)]
     /// The pixel data of the texture, in 8bit RGBA format.
+    #[br(parse_with = read_bytes, args(texture_len(width, height)))]
     pub data: Vec<u8>,
+    /// How the pixel data is encoded when saved. The data is always decoded in memory.
+    #[brw(ignore)]
+    pub encoding: TextureEncoding,
+    /// How the texture is sampled by the shader.
+    #[brw(ignore)]
+    pub sampler: TextureSampler,
+    /// The name of the texture. Must be a WGSL identifier.
+    ///
+    /// The texture is declared as `{name}` and `{name}_sampler` in the shaders, or as
+    /// `texture{index}` and `sampler{index}` if it has no name.
+    #[brw(ignore)]
+    pub name: Option<String>,
+}
+
+/// How the pixel data of a texture is stored in a wgs file.
+///
+/// Except for [`TextureEncoding::Raw`], each encoding requires the cargo feature of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TextureEncoding {
+    /// Uncompressed 8bit RGBA.
+    #[default]
+    Raw,
+    /// A PNG image.
+    Png,
+    /// A QOI image.
+    Qoi,
+    /// 8bit RGBA compressed with zstd.
+    Zstd,
+}
+
+impl TextureEncoding {
+    pub(crate) fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Raw),
+            1 => Some(Self::Png),
+            2 => Some(Self::Qoi),
+            3 => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            Self::Raw => 0,
+            Self::Png => 1,
+            Self::Qoi => 2,
+            Self::Zstd => 3,
+        }
+    }
 }
 
 impl fmt::Debug for Texture {
@@ -20,23 +77,152 @@ impl fmt::Debug for Texture {
             .field("width", &self.width)
             .field("height", &self.height)
             .field("data_len", &self.data.len())
+            .field("encoding", &self.encoding)
+            .field("sampler", &self.sampler)
+            .field("name", &self.name)
             .finish()
     }
 }
 
 impl Texture {
     /// Creates a new texture.
-    /// 
-    /// # Panics
-    /// 
-    /// - `(width * height * 4) as usize != data.len()`
-    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
-        assert!((width * height * 4) as usize == data.len());
-
-        Self {
+    ///
+    /// # Errors
+    ///
+    /// - Will return [`Error::SizeMismatch`] if `width * height * 4 != data.len()`.
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Result<Self, Error> {
+        let texture = Self {
             width,
             height,
             data,
+            encoding: TextureEncoding::Raw,
+            sampler: TextureSampler::default(),
+            name: None,
+        };
+        texture.check_size()?;
+
+        Ok(texture)
+    }
+
+    /// Checks that the data is 8bit RGBA of the size, as the fields can be set freely.
+    pub(crate) fn check_size(&self) -> Result<(), Error> {
+        if texture_len(self.width, self.height) != Some(self.data.len() as u64) {
+            return Err(Error::SizeMismatch {
+                width: self.width,
+                height: self.height,
+                len: self.data.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names the texture of the given index and its sampler are declared as.
+    pub(crate) fn bindings(&self, index: usize) -> (String, String) {
+        match &self.name {
+            Some(name) => (name.clone(), format!("{}_sampler", name)),
+            None => (format!("texture{}", index), format!("sampler{}", index)),
+        }
+    }
+}
+
+/// Checks that the names of the textures are WGSL identifiers, and that the textures
+/// and their samplers are declared as names not taken by each other, by the outputs of
+/// the buffer passes or by the built-in code.
+pub(crate) fn check_texture_names(textures: &[Texture], passes: &[Pass]) -> Result<(), Error> {
+    let mut taken = reserved_names();
+
+    for pass in passes {
+        taken.insert(pass.name.clone());
+        taken.insert(format!("{}_sampler", pass.name));
+    }
+
+    for (index, texture) in textures.iter().enumerate() {
+        if let Some(name) = texture.name.as_deref().filter(|name| !is_identifier(name)) {
+            return Err(Error::InvalidTexture(format!(
+                "Texture name `{}` is not a valid identifier",
+                name
+            )));
         }
+
+        let (name, sampler) = texture.bindings(index);
+
+        for name in [name, sampler] {
+            if !taken.insert(name.clone()) {
+                return Err(Error::InvalidTexture(format!(
+                    "Texture {} is declared as `{}`, which is taken",
+                    index, name
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WgsData;
+
+    fn texture(name: Option<&str>) -> Texture {
+        Texture {
+            name: name.map(str::to_owned),
+            ..Texture::new(1, 1, vec![0; 4]).unwrap()
+        }
+    }
+
+    #[test]
+    fn unnamed_texture_is_declared_after_its_index() {
+        assert_eq!(
+            texture(None).bindings(3),
+            ("texture3".to_owned(), "sampler3".to_owned())
+        );
+        assert_eq!(
+            texture(Some("noise")).bindings(3),
+            ("noise".to_owned(), "noise_sampler".to_owned())
+        );
+    }
+
+    #[test]
+    fn names_survive_removing_other_textures() {
+        let mut wgs = WgsData::new("names", "fn main_image() {}");
+        for _ in 0..3 {
+            wgs.add_texture(1, 1, vec![0; 4]).unwrap();
+        }
+        wgs.set_texture_name(1, Some("noise")).unwrap();
+        wgs.remove_texture(0).unwrap();
+
+        let (shader, _) = wgs.shader_frag().unwrap();
+        assert!(shader.contains("@group(1) @binding(0)\nvar noise: texture_2d<f32>;"));
+        assert!(shader.contains("@group(1) @binding(1)\nvar noise_sampler: sampler;"));
+        assert!(shader.contains("@group(2) @binding(0)\nvar texture1: texture_2d<f32>;"));
+    }
+
+    #[test]
+    fn taken_names_fail() {
+        let passes = [Pass::new("buf", "")];
+
+        for name in [
+            "1st",
+            "u",
+            "image",
+            "previous_frame_sampler",
+            "p",
+            "buf",
+            "buf_sampler",
+        ] {
+            let result = check_texture_names(&[texture(Some(name))], &passes);
+            assert!(matches!(result, Err(Error::InvalidTexture(_))), "{}", name);
+        }
+
+        let result = check_texture_names(&[texture(Some("a")), texture(Some("a"))], &[]);
+        assert!(matches!(result, Err(Error::InvalidTexture(_))));
+
+        let result = check_texture_names(&[texture(Some("sampler1")), texture(None)], &[]);
+        assert!(matches!(result, Err(Error::InvalidTexture(_))));
+
+        let result = check_texture_names(&[texture(Some("noise")), texture(None)], &passes);
+        assert!(result.is_ok());
     }
 }
@@ -0,0 +1,139 @@
+//! A minimal tokenizer of WGSL and GLSL, which is just enough to find identifiers and brackets.
+
+use std::ops::Range;
+
+/// Returns how deep in nested block comments the end of the line is, given the depth
+/// at its start.
+pub(crate) fn comment_depth(line: &str, mut depth: usize) -> usize {
+    let mut index = 0;
+
+    while index < line.len() {
+        let rest = &line[index..];
+
+        if rest.starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if depth > 0 && rest.starts_with("*/") {
+            depth -= 1;
+            index += 2;
+        } else if depth == 0 && rest.starts_with("//") {
+            break;
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    depth
+}
+
+/// Returns the identifiers of GLSL or WGSL source outside of comments, with their byte ranges.
+#[cfg(feature = "shadertoy")]
+pub(crate) fn identifiers(source: &str) -> Vec<(Range<usize>, &str)> {
+    tokens(source)
+        .into_iter()
+        .filter(|(_, token)| token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+        .collect()
+}
+
+/// Returns the tokens of GLSL or WGSL source outside of comments, with their byte ranges.
+///
+/// A token is an identifier, a number or a single character of punctuation.
+/// Block comments nest as in WGSL, which GLSL sources hardly ever rely on the other way.
+pub(crate) fn tokens(source: &str) -> Vec<(Range<usize>, &str)> {
+    tokens_at_depth(source, 0)
+}
+
+/// Returns the tokens like [`tokens`], of source which starts the given depth deep
+/// in nested block comments, e.g. a line in the middle of a comment.
+pub(crate) fn tokens_at_depth(source: &str, depth: usize) -> Vec<(Range<usize>, &str)> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut index = block_comment_end(source, 0, depth);
+
+    while index < bytes.len() {
+        let start = index;
+
+        if source[index..].starts_with("//") {
+            index = source[index..]
+                .find('\n')
+                .map_or(bytes.len(), |end| index + end);
+        } else if source[index..].starts_with("/*") {
+            index = block_comment_end(source, index, 0);
+        } else if bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_' {
+            // Numbers like `1e5f` are taken as a whole.
+            while index < bytes.len()
+                && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_')
+            {
+                index += 1;
+            }
+
+            tokens.push((start..index, &source[start..index]));
+        } else {
+            index += source[index..].chars().next().map_or(1, char::len_utf8);
+
+            if !bytes[start].is_ascii_whitespace() {
+                tokens.push((start..index, &source[start..index]));
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Returns where the source at the index, which is the given depth deep in nested
+/// block comments, gets out of them, skipping the block comments starting there as well.
+/// Returns the end of the source if it never does.
+fn block_comment_end(source: &str, mut index: usize, mut depth: usize) -> usize {
+    loop {
+        let rest = &source[index..];
+
+        if rest.starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if depth == 0 || rest.is_empty() {
+            return index;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            index += 2;
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<&str> {
+        tokens(source).into_iter().map(|(_, token)| token).collect()
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(texts("a // b\nc /* d */ e"), ["a", "c", "e"]);
+        assert_eq!(texts("a /* b"), ["a"]);
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        assert_eq!(texts("a /* b /* c */ d */ e"), ["a", "e"]);
+        assert_eq!(tokens_at_depth("b */ c */ d /* e", 2), [(10..11, "d")]);
+        assert_eq!(comment_depth("a /* b /* c */ d", 0), 1);
+        assert_eq!(comment_depth("d */ e // /*", 1), 0);
+    }
+
+    #[test]
+    fn tokens_keep_their_ranges() {
+        let source = "let x1 = 1e5f;";
+        let tokens = tokens(source);
+
+        assert_eq!(
+            tokens.iter().map(|(_, token)| *token).collect::<Vec<_>>(),
+            ["let", "x1", "=", "1e5f", ";"]
+        );
+        assert!(tokens
+            .iter()
+            .all(|(range, token)| &source[range.clone()] == *token));
+    }
+}
@@ -0,0 +1,12 @@
+use crate::{meta::Meta, texture::Texture};
+use binrw::{binread, helpers::count, NullString};
+
+/// The layout of a wgs 1 file.
+#[binread]
+#[br(little)]
+pub struct WgsDataV1 {
+    pub meta: Meta,
+    pub frag: NullString,
+    #[br(parse_with = count(meta.texture_count.into()))]
+    pub textures: Vec<Texture>,
+}
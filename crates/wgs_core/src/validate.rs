@@ -0,0 +1,167 @@
+use crate::source_map::{SourceMap, SourceOrigin};
+use std::{error::Error, fmt, ops::Range};
+
+/// A problem found in a shader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// The summary of the problem.
+    pub message: String,
+    /// The regions of the shader source related to the problem. The first one is the primary one.
+    pub labels: Vec<Label>,
+    /// Additional details, e.g. the underlying causes of the problem.
+    pub notes: Vec<String>,
+}
+
+/// A region of the shader source attached to a [`Diagnostic`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    /// The source the region belongs to.
+    pub origin: SourceOrigin,
+    /// The byte range of the region in the source it belongs to.
+    ///
+    /// For [`SourceOrigin::BuiltIn`], it's the byte range in the generated shader.
+    pub span: Range<usize>,
+    /// The line where the region starts, starting from 1.
+    pub line: u32,
+    /// The column in bytes where the region starts, starting from 1.
+    pub column: u32,
+    /// The description of the region.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        for label in &self.labels {
+            match &label.origin {
+                SourceOrigin::BuiltIn => write!(f, "\n  at built-in code")?,
+                SourceOrigin::Frag => write!(f, "\n  at {}:{}", label.line, label.column)?,
+                SourceOrigin::Include(name) => write!(
+                    f,
+                    "\n  at include `{}` {}:{}",
+                    name, label.line, label.column
+                )?,
+                SourceOrigin::Pass(name) => {
+                    write!(f, "\n  at pass `{}` {}:{}", name, label.line, label.column)?
+                }
+            }
+
+            if !label.message.is_empty() {
+                write!(f, ": {}", label.message)?;
+            }
+        }
+
+        for note in &self.notes {
+            write!(f, "\n  note: {}", note)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic without labels and notes.
+    pub(crate) fn new(message: &str) -> Self {
+        Self {
+            message: message.to_owned(),
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+}
+
+impl Label {
+    pub(crate) fn new(
+        source: &str,
+        source_map: &SourceMap,
+        span: naga::Span,
+        message: &str,
+    ) -> Option<Self> {
+        let location = source_map.locate(source, span.to_range()?);
+
+        Some(Self {
+            origin: location.origin,
+            span: location.span,
+            line: location.line,
+            column: location.column,
+            message: message.to_owned(),
+        })
+    }
+}
+
+/// Parses and validates a complete WGSL shader without a GPU.
+///
+/// Returns the diagnostics if the shader fails to compile.
+/// The whole shader is regarded as [`SourceOrigin::Frag`].
+pub fn validate_shader(source: &str) -> Result<(), Vec<Diagnostic>> {
+    let mut source_map = SourceMap::default();
+    source_map.push(0..source.len(), SourceOrigin::Frag, 0, source);
+
+    validate_shader_with_source_map(source, &source_map)
+}
+
+/// Parses and validates a complete WGSL shader without a GPU,
+/// mapping the spans of the diagnostics with the [`SourceMap`].
+///
+/// Returns the diagnostics if the shader fails to compile.
+pub fn validate_shader_with_source_map(
+    source: &str,
+    source_map: &SourceMap,
+) -> Result<(), Vec<Diagnostic>> {
+    parse_shader(source, source_map)?;
+
+    Ok(())
+}
+
+/// Parses and validates a complete WGSL shader, mapping the spans of the diagnostics
+/// with the [`SourceMap`].
+pub(crate) fn parse_shader(
+    source: &str,
+    source_map: &SourceMap,
+) -> Result<(naga::Module, naga::valid::ModuleInfo), Vec<Diagnostic>> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|err| {
+        vec![Diagnostic {
+            message: err.message().to_owned(),
+            labels: err
+                .labels()
+                .filter_map(|(span, message)| Label::new(source, source_map, span, message))
+                .collect(),
+            notes: vec![],
+        }]
+    })?;
+
+    let info = validate_module(&module, source, source_map)?;
+
+    Ok((module, info))
+}
+
+/// Validates a parsed module, mapping the spans of the diagnostics with the [`SourceMap`].
+pub(crate) fn validate_module(
+    module: &naga::Module,
+    source: &str,
+    source_map: &SourceMap,
+) -> Result<naga::valid::ModuleInfo, Vec<Diagnostic>> {
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(module)
+    .map_err(|err| {
+        let mut notes = vec![];
+        let mut source_err = err.as_inner().source();
+        while let Some(inner) = source_err {
+            notes.push(inner.to_string());
+            source_err = inner.source();
+        }
+
+        vec![Diagnostic {
+            message: err.as_inner().to_string(),
+            labels: err
+                .spans()
+                .filter_map(|(span, message)| Label::new(source, source_map, *span, message))
+                .collect(),
+            notes,
+        }]
+    })
+}
@@ -1,13 +1,31 @@
 use anyhow::Result;
-use wgs_core::WgsData;
+use wgs_core::{Library, Param, ParamValue, Pass, TextureSampler, WgsData};
 
 /// A basic trait for wgs runtime.
 pub trait RuntimeExt {
+    /// Adds a parameter to wgs. Takes effect after compiling.
+    fn add_param(&mut self, param: Param);
+
+    /// Adds a buffer pass to wgs. Takes effect after compiling.
+    fn add_pass(&mut self, pass: Pass);
+
     /// Adds a texture to wgs.
-    fn add_texture(&mut self, width: u32, height: u32, buffer: Vec<u8>);
+    fn add_texture(&mut self, width: u32, height: u32, buffer: Vec<u8>) -> Result<()>;
+
+    /// Changes the parameter of the given index in wgs. Takes effect after compiling.
+    fn change_param(&mut self, index: usize, param: Param) -> Result<()>;
+
+    /// Changes the buffer pass of the given index in wgs. Takes effect after compiling.
+    fn change_pass(&mut self, index: usize, pass: Pass) -> Result<()>;
 
     /// Changes the texture of the given index in wgs.
-    fn change_texture(&mut self, index: usize, width: u32, height: u32, buffer: Vec<u8>);
+    fn change_texture(
+        &mut self,
+        index: usize,
+        width: u32,
+        height: u32,
+        buffer: Vec<u8>,
+    ) -> Result<()>;
 
     /// Compiles wgs manually.
     fn compile(&mut self) -> Result<()>;
@@ -18,12 +36,21 @@ pub trait RuntimeExt {
     /// Pauses the runtime.
     fn pause(&mut self);
 
+    /// Removes a parameter from wgs. Takes effect after compiling.
+    fn remove_param(&mut self, index: usize) -> Result<()>;
+
+    /// Removes a buffer pass from wgs. Takes effect after compiling.
+    fn remove_pass(&mut self, index: usize) -> Result<()>;
+
     /// Removes a texture from wgs.
-    fn remove_texture(&mut self, index: usize);
+    fn remove_texture(&mut self, index: usize) -> Result<()>;
 
     /// Do the rendering.
     fn render(&mut self) -> Result<()>;
 
+    /// Resets the parameters to their default values.
+    fn reset_params(&mut self);
+
     /// Resize the runtime.
     fn resize(&mut self, width: f32, height: f32);
 
@@ -33,6 +60,18 @@ pub trait RuntimeExt {
     /// Resumes the runtime.
     fn resume(&mut self);
 
+    /// Sets the WGSL modules provided by the host, which the frags can `#include`
+    /// along with the ones embedded in wgs. Takes effect after compiling.
+    fn set_library(&mut self, library: Library);
+
+    /// Sets the value of the parameter of the given name. A float is clamped to its range.
+    ///
+    /// The value lasts until the parameter is reset, removed or changed to another type.
+    fn set_param(&mut self, name: &str, value: ParamValue) -> Result<()>;
+
+    /// Sets how the texture of the given index in wgs is sampled by the shader.
+    fn set_texture_sampler(&mut self, index: usize, sampler: TextureSampler) -> Result<()>;
+
     /// Sets the content of the editable part of the fragment shader in wgs.
     fn set_wgs_frag(&mut self, shader_frag: &str);
 
@@ -0,0 +1,278 @@
+use crate::viewport::Viewport;
+use std::borrow::Cow;
+
+/// The format of the textures the image is rendered into.
+pub const FEEDBACK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+const BLIT_SHADER: &str = include_str!("./assets/blit.wgsl");
+
+/// The ping-pong render target of the image.
+///
+/// Each frame, the image is rendered into one of the two textures while the other one,
+/// holding the image of the previous frame, is exposed to wgs as `previous_frame`.
+/// The image is then copied onto the target of the runtime.
+pub struct Feedback {
+    blit_bind_group_layout: wgpu::BindGroupLayout,
+    blit_bind_groups: Vec<wgpu::BindGroup>,
+    blit_pipeline: wgpu::RenderPipeline,
+    /// Which of the two textures is rendered into in the current frame.
+    frame_parity: usize,
+    sampler: wgpu::Sampler,
+    /// The size of the textures. Textures are recreated, thus cleared, when it changes.
+    size: (u32, u32),
+    textures: Vec<wgpu::Texture>,
+    /// The uniform bind groups exposing the previous frame, one per frame parity.
+    uniform_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl Feedback {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        param_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let blit_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Blit Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&blit_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blit Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::from(BLIT_SHADER)),
+        });
+
+        let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blit Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // Repeats, as `frag_coord.x` runs from `-resolution.x` to zero.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Feedback Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..wgpu::SamplerDescriptor::default()
+        });
+
+        let mut feedback = Self {
+            blit_bind_group_layout,
+            blit_bind_groups: vec![],
+            blit_pipeline,
+            frame_parity: 0,
+            sampler,
+            size: (0, 0),
+            textures: vec![],
+            uniform_bind_groups: vec![],
+        };
+
+        feedback.prepare(
+            device,
+            (1, 1),
+            uniform_bind_group_layout,
+            uniform_buffer,
+            param_buffer,
+        );
+
+        feedback
+    }
+
+    /// Copies the image of the current frame onto the view and moves on to the next frame.
+    pub fn blit(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        viewport: Option<&Viewport>,
+    ) {
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Blit Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            if let Some(viewport) = viewport {
+                render_pass.set_viewport(
+                    viewport.x,
+                    viewport.y,
+                    viewport.width,
+                    viewport.height,
+                    viewport.min_depth,
+                    viewport.max_depth,
+                );
+            }
+
+            render_pass.set_pipeline(&self.blit_pipeline);
+
+            render_pass.set_bind_group(0, &self.blit_bind_groups[self.frame_parity], &[]);
+
+            render_pass.draw(0..3, 0..1);
+        }
+
+        self.frame_parity = 1 - self.frame_parity;
+    }
+
+    /// Clears the image of the previous frame.
+    ///
+    /// Also needed when the uniform buffers are recreated, to bind the new ones.
+    pub fn clear(&mut self) {
+        self.size = (0, 0);
+    }
+
+    /// Recreates the textures if they are cleared or don't match the size.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        size: (u32, u32),
+        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        param_buffer: &wgpu::Buffer,
+    ) {
+        let size = (size.0.max(1), size.1.max(1));
+        if self.size == size {
+            return;
+        }
+
+        self.textures = (0..2)
+            .map(|_| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Feedback Texture"),
+                    size: wgpu::Extent3d {
+                        width: size.0,
+                        height: size.1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: FEEDBACK_FORMAT,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+            })
+            .collect();
+
+        let views = self
+            .textures
+            .iter()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            .collect::<Vec<_>>();
+
+        self.blit_bind_groups = views
+            .iter()
+            .map(|view| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Blit Bind Group"),
+                    layout: &self.blit_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        // The frame of parity `n` reads the texture rendered in the frame of parity `1 - n`.
+        self.uniform_bind_groups = views
+            .iter()
+            .rev()
+            .map(|previous_view| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Uniform Bind Group"),
+                    layout: uniform_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(previous_view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: param_buffer.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        self.frame_parity = 0;
+        self.size = size;
+    }
+
+    /// Returns the uniform bind group of the current frame.
+    pub fn uniform_bind_group(&self) -> &wgpu::BindGroup {
+        &self.uniform_bind_groups[self.frame_parity]
+    }
+
+    /// Returns the view of the texture the image of the current frame is rendered into.
+    pub fn view(&self) -> wgpu::TextureView {
+        self.textures[self.frame_parity].create_view(&wgpu::TextureViewDescriptor::default())
+    }
+}
@@ -86,6 +86,31 @@
 //! }
 //! ```
 //!
+//! ### Render offscreen
+//!
+//! A runtime can also render into an internally owned texture without any window,
+//! which is handy on build servers that have no display.
+//!
+//! ```no_run
+//! use wgs_core::WgsData;
+//! use wgs_runtime_wgpu::{wgpu, Runtime, RuntimeExt};
+//!
+//! let mut runtime = futures::executor::block_on(Runtime::new_offscreen(
+//!     WgsData::default(),
+//!     None,
+//!     512,
+//!     512,
+//!     wgpu::TextureFormat::Rgba8UnormSrgb,
+//! ))
+//! .unwrap();
+//!
+//! runtime.frame_start().unwrap();
+//!
+//! runtime.render().unwrap();
+//!
+//! runtime.frame_finish().unwrap();
+//! ```
+//!
 //! ### Integrate with Web
 //!
 //! `wgs_runtime_wgpu` also compiles for Wasm32 and can be run on Web.
@@ -94,8 +119,13 @@
 //! or use a high-level library [`wgs-player`](https://github.com/fralonra/wgs-player).
 //!
 
+mod feedback;
+mod pass;
 mod pausable_instant;
 mod runtime;
+#[cfg(not(target_arch = "wasm32"))]
+mod snapshot;
+mod target;
 mod uniform;
 mod viewport;
 #[cfg(target_arch = "wasm32")]
@@ -104,5 +134,7 @@ mod web;
 pub use wgpu;
 
 pub use runtime::Runtime;
+#[cfg(not(target_arch = "wasm32"))]
+pub use snapshot::{render_snapshot, render_snapshot_png, write_png, Snapshot};
 pub use viewport::Viewport;
 pub use wgs_runtime_base::RuntimeExt;
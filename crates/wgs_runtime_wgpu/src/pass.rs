@@ -0,0 +1,317 @@
+use crate::runtime::{build_pipeline, TextureBinding};
+use anyhow::{bail, Result};
+use wgs_core::{Library, PassInput, WgsData};
+
+/// The format of the textures the buffer passes render into.
+const PASS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// The buffer passes of wgs, rendered before the image pass.
+pub struct Passes {
+    /// The layout of the pass outputs read by the image pass. `None` if there is no pass.
+    pub bind_group_layout: Option<wgpu::BindGroupLayout>,
+    /// Which of the two targets of each pass is rendered into in the current frame.
+    frame_parity: usize,
+    /// The pipelines in the order to execute them.
+    pipelines: Vec<PassPipeline>,
+    sampler: wgpu::Sampler,
+    /// The size of the targets. Targets are recreated, thus cleared, when it changes.
+    target_size: (u32, u32),
+}
+
+/// An input of a buffer pass.
+enum Input {
+    /// The pass at the given position of [`Passes::pipelines`].
+    Pass(usize),
+    /// The texture of the given index.
+    Texture(usize),
+}
+
+struct PassPipeline {
+    /// The index of the pass in wgs.
+    index: usize,
+    input_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    inputs: Vec<Input>,
+    name: String,
+    pipeline: wgpu::RenderPipeline,
+    /// The two textures rendered into alternately,
+    /// so that a pass can read its own output of the previous frame.
+    targets: Vec<wgpu::Texture>,
+}
+
+impl Passes {
+    /// Builds the pipelines of the passes in wgs, including the modules of the library.
+    pub fn new(
+        wgs: &WgsData,
+        library: &Library,
+        device: &wgpu::Device,
+        shader_vert: &str,
+        uniform_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<Self> {
+        let order = wgs.pass_order()?;
+
+        let passes = wgs.passes_ref();
+
+        let mut pipelines = vec![];
+        for &index in &order {
+            let pass = &passes[index];
+
+            let inputs = pass
+                .inputs
+                .iter()
+                .map(|input| match input {
+                    PassInput::Pass(name) => Input::Pass(
+                        order
+                            .iter()
+                            .position(|&index| passes[index].name == *name)
+                            .unwrap(),
+                    ),
+                    PassInput::Texture(index) => Input::Texture(*index),
+                })
+                .collect::<Vec<_>>();
+
+            let input_bind_group_layout =
+                (!inputs.is_empty()).then(|| create_input_bind_group_layout(device, inputs.len()));
+
+            let mut bind_group_layouts = vec![uniform_bind_group_layout];
+            bind_group_layouts.extend(input_bind_group_layout.as_ref());
+
+            let pipeline = build_pipeline(
+                &wgs.shader_pass_with_library(index, library)?.0,
+                shader_vert,
+                &bind_group_layouts,
+                device,
+                PASS_FORMAT,
+            )?;
+
+            pipelines.push(PassPipeline {
+                index,
+                input_bind_group_layout,
+                inputs,
+                name: pass.name.clone(),
+                pipeline,
+                targets: vec![],
+            });
+        }
+
+        let bind_group_layout =
+            (!passes.is_empty()).then(|| create_input_bind_group_layout(device, passes.len()));
+
+        // Repeats like the one of the previous frame, as `frag_coord.x` runs from
+        // `-resolution.x` to zero.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Pass Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..wgpu::SamplerDescriptor::default()
+        });
+
+        Ok(Self {
+            bind_group_layout,
+            frame_parity: 0,
+            pipelines,
+            sampler,
+            target_size: (0, 0),
+        })
+    }
+
+    /// Clears the outputs of the passes.
+    pub fn clear(&mut self) {
+        self.target_size = (0, 0);
+    }
+
+    /// Records the rendering of the passes into the encoder.
+    ///
+    /// Returns the bind group of the pass outputs for the image pass.
+    /// `None` if there is no pass.
+    ///
+    /// # Errors
+    ///
+    /// - Will return an error if a pass reads a texture that no longer exists.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        uniform_bind_group: &wgpu::BindGroup,
+        textures: &[TextureBinding],
+        size: (u32, u32),
+    ) -> Result<Option<wgpu::BindGroup>> {
+        let Some(bind_group_layout) = &self.bind_group_layout else {
+            return Ok(None);
+        };
+
+        let size = (size.0.max(1), size.1.max(1));
+        if self.target_size != size {
+            for pipeline in &mut self.pipelines {
+                pipeline.targets = (0..2)
+                    .map(|_| create_target(device, size.0, size.1))
+                    .collect();
+            }
+
+            self.frame_parity = 0;
+            self.target_size = size;
+        }
+
+        let current = self.frame_parity;
+        let previous = 1 - current;
+
+        for (position, pass) in self.pipelines.iter().enumerate() {
+            let input_bind_group = match &pass.input_bind_group_layout {
+                Some(layout) => {
+                    let mut views = vec![];
+                    for input in &pass.inputs {
+                        views.push(match input {
+                            Input::Pass(input) => {
+                                // The passes before have been rendered in this frame.
+                                let parity = if *input < position { current } else { previous };
+
+                                (
+                                    create_view(&self.pipelines[*input].targets[parity]),
+                                    &self.sampler,
+                                )
+                            }
+                            Input::Texture(index) => {
+                                let Some(texture) = textures.get(*index) else {
+                                    bail!(
+                                        "Texture {} read by pass `{}` not found.",
+                                        index,
+                                        pass.name
+                                    )
+                                };
+
+                                (texture.create_view(), &texture.sampler)
+                            }
+                        });
+                    }
+
+                    Some(create_input_bind_group(device, layout, &views))
+                }
+                None => None,
+            };
+
+            let view = create_view(&pass.targets[current]);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Buffer Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&pass.pipeline);
+
+            render_pass.set_bind_group(0, uniform_bind_group, &[]);
+
+            if let Some(input_bind_group) = &input_bind_group {
+                render_pass.set_bind_group(1, input_bind_group, &[]);
+            }
+
+            render_pass.draw(0..3, 0..1);
+        }
+
+        let mut pipelines = self.pipelines.iter().collect::<Vec<_>>();
+        pipelines.sort_by_key(|pass| pass.index);
+
+        let views = pipelines
+            .iter()
+            .map(|pass| (create_view(&pass.targets[current]), &self.sampler))
+            .collect::<Vec<_>>();
+
+        self.frame_parity = previous;
+
+        Ok(Some(create_input_bind_group(
+            device,
+            bind_group_layout,
+            &views,
+        )))
+    }
+}
+
+/// Creates a bind group of textures and their samplers, binding them in pairs.
+fn create_input_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    inputs: &[(wgpu::TextureView, &wgpu::Sampler)],
+) -> wgpu::BindGroup {
+    let entries = (0..)
+        .zip(inputs)
+        .flat_map(|(index, (view, sampler))| {
+            [
+                wgpu::BindGroupEntry {
+                    binding: index * 2,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: index * 2 + 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &entries,
+        label: Some("Pass Input Bind Group"),
+    })
+}
+
+fn create_input_bind_group_layout(device: &wgpu::Device, count: usize) -> wgpu::BindGroupLayout {
+    let entries = (0..count as u32)
+        .flat_map(|index| {
+            [
+                wgpu::BindGroupLayoutEntry {
+                    binding: index * 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: index * 2 + 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &entries,
+        label: Some("Pass Input Bind Group Layout"),
+    })
+}
+
+fn create_target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Pass Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: PASS_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+fn create_view(texture: &wgpu::Texture) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
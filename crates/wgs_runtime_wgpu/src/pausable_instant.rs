@@ -43,6 +43,11 @@ impl PausableInstant {
         self.instant = Instant::now();
     }
 
+    pub fn set_elapsed(&mut self, duration: Duration) {
+        self.instant = Instant::now();
+        self.stored_duration = duration;
+    }
+
     fn reset(&mut self) {
         self.instant = Instant::now();
         self.stored_duration = Duration::ZERO;
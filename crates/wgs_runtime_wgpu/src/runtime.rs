@@ -1,8 +1,20 @@
-use crate::{pausable_instant::PausableInstant, uniform::Uniform, viewport::Viewport};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::snapshot::Snapshot;
+use crate::{
+    feedback::{Feedback, FEEDBACK_FORMAT},
+    pass::Passes,
+    pausable_instant::PausableInstant,
+    target::{create_offscreen_texture, offscreen_configuration, Frame, Target},
+    uniform::Uniform,
+    viewport::Viewport,
+};
 use anyhow::{bail, Result};
-use std::borrow::Cow;
+use std::{borrow::Cow, time::Duration};
 use wgpu::util::DeviceExt;
-use wgs_core::{concat_shader_frag, WgsData, VERT_DEFAULT};
+use wgs_core::{
+    pack_params, AddressMode, FilterMode, Library, Param, ParamValue, Pass, Texture,
+    TextureSampler, WgsData, VERT_DEFAULT,
+};
 use wgs_runtime_base::RuntimeExt;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -11,27 +23,35 @@ const DATA_PER_PIXEL: u32 = 4;
 const U8_SIZE: u32 = std::mem::size_of::<u8>() as u32;
 const UNIFORM_GROUP_ID: u32 = 0;
 
+#[cfg(not(target_arch = "wasm32"))]
+type CaptureCallback<'w> = Box<dyn FnOnce(&mut Runtime<'w>, u32, u32, Vec<u8>)>;
+
 /// The wgpu wgs runtime.
 pub struct Runtime<'w> {
     #[cfg(not(target_arch = "wasm32"))]
-    captured_callback: Option<(Viewport, Box<dyn FnOnce(&mut Self, u32, u32, Vec<u8>)>)>,
+    captured_callback: Option<(Viewport, CaptureCallback<'w>)>,
     device: wgpu::Device,
+    feedback: Feedback,
+    frame: Option<Frame>,
     height: f32,
     #[cfg(not(target_arch = "wasm32"))]
     is_capture_supported: bool,
     is_paused: bool,
+    /// The WGSL modules provided by the host.
+    library: Library,
+    param_buffer: wgpu::Buffer,
+    /// The current values of the parameters, by name.
+    param_values: Vec<(String, ParamValue)>,
+    passes: Passes,
     pipeline: wgpu::RenderPipeline,
     queue: wgpu::Queue,
-    sampler: wgpu::Sampler,
     shader_vert: String,
-    surface: wgpu::Surface<'w>,
     surface_configuration: wgpu::SurfaceConfiguration,
-    surface_texture: Option<wgpu::SurfaceTexture>,
-    texture_bind_groups: Vec<(wgpu::BindGroupLayout, wgpu::BindGroup)>,
+    target: Target<'w>,
     texture_view: Option<wgpu::TextureView>,
+    textures: Vec<TextureBinding>,
     time_instant: PausableInstant,
     uniform: Uniform,
-    uniform_bind_group: wgpu::BindGroup,
     uniform_bind_group_layout: wgpu::BindGroupLayout,
     uniform_buffer: wgpu::Buffer,
     viewport: Option<Viewport>,
@@ -39,42 +59,77 @@ pub struct Runtime<'w> {
     width: f32,
 }
 
+/// The GPU resources of a texture in wgs.
+pub struct TextureBinding {
+    bind_group: wgpu::BindGroup,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pub sampler: wgpu::Sampler,
+    texture: wgpu::Texture,
+}
+
 impl RuntimeExt for Runtime<'_> {
-    fn add_texture(&mut self, width: u32, height: u32, buffer: Vec<u8>) {
-        self.texture_bind_groups.push(create_texture(
-            &self.device,
-            &self.queue,
-            &self.sampler,
-            width,
-            height,
-            &buffer,
-        ));
+    fn add_param(&mut self, param: Param) {
+        self.wgs.add_param(param);
+    }
 
-        self.wgs.add_texture(width, height, buffer);
+    fn add_pass(&mut self, pass: Pass) {
+        self.wgs.add_pass(pass);
     }
 
-    fn change_texture(&mut self, index: usize, width: u32, height: u32, buffer: Vec<u8>) {
-        self.texture_bind_groups[index] = create_texture(
-            &self.device,
-            &self.queue,
-            &self.sampler,
-            width,
-            height,
-            &buffer,
-        );
+    fn add_texture(&mut self, width: u32, height: u32, buffer: Vec<u8>) -> Result<()> {
+        self.wgs.add_texture(width, height, buffer)?;
 
-        self.wgs.change_texture(index, width, height, buffer);
+        let texture = self.wgs.textures_ref().last().unwrap();
+        self.textures
+            .push(create_texture(&self.device, &self.queue, texture));
+
+        Ok(())
+    }
+
+    fn change_param(&mut self, index: usize, param: Param) -> Result<()> {
+        Ok(self.wgs.change_param(index, param)?)
+    }
+
+    fn change_pass(&mut self, index: usize, pass: Pass) -> Result<()> {
+        Ok(self.wgs.change_pass(index, pass)?)
+    }
+
+    fn change_texture(
+        &mut self,
+        index: usize,
+        width: u32,
+        height: u32,
+        buffer: Vec<u8>,
+    ) -> Result<()> {
+        self.wgs.change_texture(index, width, height, buffer)?;
+
+        let texture = &self.wgs.textures_ref()[index];
+        self.textures[index] = create_texture(&self.device, &self.queue, texture);
+
+        Ok(())
     }
 
     fn compile(&mut self) -> Result<()> {
-        self.pipeline = prepare_wgs_pipeline(
+        let passes = Passes::new(
             &self.wgs,
+            &self.library,
+            &self.device,
+            &self.shader_vert,
+            &self.uniform_bind_group_layout,
+        )?;
+
+        self.pipeline = prepare_wgs_pipeline(
+            &self.wgs.shader_frag_with_library(&self.library)?.0,
             &self.device,
-            self.surface_configuration.format,
+            FEEDBACK_FORMAT,
             &self.shader_vert,
-            &self.texture_bind_groups,
+            &self.textures,
+            &passes,
             &self.uniform_bind_group_layout,
         )?;
+        self.passes = passes;
+
+        self.prepare_params();
 
         self.restart();
 
@@ -82,20 +137,23 @@ impl RuntimeExt for Runtime<'_> {
     }
 
     fn load(&mut self, wgs: wgs_core::WgsData) -> Result<()> {
-        let (texture_bind_groups, pipeline) = prepare_wgs(
+        let (textures, passes, pipeline) = prepare_wgs(
             &wgs,
+            &self.library,
             &self.device,
             &self.queue,
-            &self.sampler,
-            self.surface_configuration.format,
+            FEEDBACK_FORMAT,
             &self.shader_vert,
             &self.uniform_bind_group_layout,
         )?;
 
-        self.texture_bind_groups = texture_bind_groups;
+        self.passes = passes;
         self.pipeline = pipeline;
+        self.textures = textures;
         self.wgs = wgs;
 
+        self.prepare_params();
+
         self.restart();
 
         Ok(())
@@ -111,10 +169,20 @@ impl RuntimeExt for Runtime<'_> {
         self.time_instant.pause();
     }
 
-    fn remove_texture(&mut self, index: usize) {
-        self.texture_bind_groups.remove(index);
+    fn remove_param(&mut self, index: usize) -> Result<()> {
+        Ok(self.wgs.remove_param(index)?)
+    }
+
+    fn remove_pass(&mut self, index: usize) -> Result<()> {
+        Ok(self.wgs.remove_pass(index)?)
+    }
+
+    fn remove_texture(&mut self, index: usize) -> Result<()> {
+        self.wgs.remove_texture(index)?;
 
-        self.wgs.remove_texture(index);
+        self.textures.remove(index);
+
+        Ok(())
     }
 
     fn render(&mut self) -> Result<()> {
@@ -130,25 +198,46 @@ impl RuntimeExt for Runtime<'_> {
                 label: Some("Render Encoder"),
             });
 
-        if let Some(viewport) = &self.viewport {
-            self.uniform.resolution = [viewport.width, viewport.height];
-        } else {
-            self.uniform.resolution = [self.width, self.height]
-        }
+        self.uniform.resolution = self.resolution();
 
         self.uniform.time = self.time_instant.elapsed().as_secs_f32();
 
         self.queue
             .write_buffer(&self.uniform_buffer, 0, self.uniform.as_bytes());
 
+        if !self.param_values.is_empty() {
+            self.queue
+                .write_buffer(&self.param_buffer, 0, &self.packed_params());
+        }
+
+        let size = self.render_size();
+
+        self.feedback.prepare(
+            &self.device,
+            size,
+            &self.uniform_bind_group_layout,
+            &self.uniform_buffer,
+            &self.param_buffer,
+        );
+
+        let pass_bind_group = self.passes.render(
+            &self.device,
+            &mut encoder,
+            self.feedback.uniform_bind_group(),
+            &self.textures,
+            size,
+        )?;
+
         {
+            let feedback_view = self.feedback.view();
+
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
+                    view: &feedback_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -157,44 +246,59 @@ impl RuntimeExt for Runtime<'_> {
                 occlusion_query_set: None,
             });
 
-            if let Some(viewport) = &self.viewport {
-                render_pass.set_viewport(
-                    viewport.x,
-                    viewport.y,
-                    viewport.width,
-                    viewport.height,
-                    viewport.min_depth,
-                    viewport.max_depth,
-                );
-            }
-
             render_pass.set_pipeline(&self.pipeline);
 
-            render_pass.set_bind_group(UNIFORM_GROUP_ID, &self.uniform_bind_group, &[]);
+            render_pass.set_bind_group(UNIFORM_GROUP_ID, self.feedback.uniform_bind_group(), &[]);
 
-            let mut index = 1;
-            for (_, bind_group) in &self.texture_bind_groups {
-                render_pass.set_bind_group(index, bind_group, &[]);
-                index += 1;
+            for (index, texture) in (1..).zip(self.textures.iter()) {
+                render_pass.set_bind_group(index, &texture.bind_group, &[]);
+            }
+
+            if let Some(pass_bind_group) = &pass_bind_group {
+                render_pass.set_bind_group(self.textures.len() as u32 + 1, pass_bind_group, &[]);
             }
 
             render_pass.draw(0..3, 0..1);
         }
 
+        self.feedback
+            .blit(&mut encoder, view, self.viewport.as_ref());
+
         self.queue.submit(Some(encoder.finish()));
 
         Ok(())
     }
 
+    fn reset_params(&mut self) {
+        for (name, value) in &mut self.param_values {
+            if let Some(param) = self
+                .wgs
+                .params_ref()
+                .iter()
+                .find(|param| param.name == *name)
+            {
+                *value = param.default_value();
+            }
+        }
+    }
+
     fn resize(&mut self, width: f32, height: f32) {
         self.surface_configuration.width = width as u32;
         self.surface_configuration.height = height as u32;
 
-        self.surface
+        self.target
             .configure(&self.device, &self.surface_configuration);
 
         self.width = width;
         self.height = height;
+
+        self.feedback.prepare(
+            &self.device,
+            self.render_size(),
+            &self.uniform_bind_group_layout,
+            &self.uniform_buffer,
+            &self.param_buffer,
+        );
     }
 
     fn restart(&mut self) {
@@ -202,6 +306,10 @@ impl RuntimeExt for Runtime<'_> {
 
         self.time_instant = PausableInstant::now();
 
+        self.feedback.clear();
+
+        self.passes.clear();
+
         let resolution = self.uniform.resolution;
 
         self.uniform = Uniform::default();
@@ -218,6 +326,47 @@ impl RuntimeExt for Runtime<'_> {
         self.time_instant.resume();
     }
 
+    fn set_param(&mut self, name: &str, value: ParamValue) -> Result<()> {
+        let Some(param) = self
+            .wgs
+            .params_ref()
+            .iter()
+            .find(|param| param.name == name)
+        else {
+            bail!("Param `{}` not found.", name)
+        };
+
+        let value = param.check(value)?;
+
+        let Some((_, current)) = self
+            .param_values
+            .iter_mut()
+            .find(|(current_name, current)| {
+                current_name == name
+                    && std::mem::discriminant(current) == std::mem::discriminant(&value)
+            })
+        else {
+            bail!("Param `{}` has changed. Compile wgs first.", name)
+        };
+
+        *current = value;
+
+        Ok(())
+    }
+
+    fn set_library(&mut self, library: Library) {
+        self.library = library;
+    }
+
+    fn set_texture_sampler(&mut self, index: usize, sampler: TextureSampler) -> Result<()> {
+        self.wgs.set_texture_sampler(index, sampler)?;
+
+        let texture = &self.wgs.textures_ref()[index];
+        self.textures[index] = create_texture(&self.device, &self.queue, texture);
+
+        Ok(())
+    }
+
     fn set_wgs_frag(&mut self, shader_frag: &str) {
         self.wgs.set_frag(shader_frag)
     }
@@ -293,24 +442,99 @@ impl<'w> Runtime<'w> {
     ) -> Result<Self> {
         let (surface_configuration, device, queue) = init_adapter(&instance, &surface).await?;
 
+        Self::with_device_and_target(
+            wgs,
+            viewport,
+            device,
+            queue,
+            surface_configuration,
+            Target::Surface(surface),
+        )
+    }
+
+    /// Creates a new runtime which renders into an internally owned texture instead of a surface.
+    ///
+    /// Useful for rendering without a display, e.g. thumbnails or images on a build server.
+    /// Falls back to the software adapter if no hardware adapter is available.
+    pub async fn new_offscreen(
+        wgs: WgsData,
+        viewport: Option<Viewport>,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self> {
+        let instance = init_instance();
+
+        Self::with_instance_offscreen(wgs, viewport, instance, width, height, format).await
+    }
+
+    /// Creates a new offscreen runtime with given [`wgpu::Instance`].
+    ///
+    /// See [`Self::new_offscreen`].
+    pub async fn with_instance_offscreen(
+        wgs: WgsData,
+        viewport: Option<Viewport>,
+        instance: wgpu::Instance,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self> {
+        let (device, queue) = init_adapter_offscreen(&instance).await?;
+
+        let surface_configuration = offscreen_configuration(width, height, format);
+
+        let texture = create_offscreen_texture(&device, &surface_configuration);
+
+        let mut runtime = Self::with_device_and_target(
+            wgs,
+            viewport,
+            device,
+            queue,
+            surface_configuration,
+            Target::Offscreen(texture),
+        )?;
+
+        runtime.width = width as f32;
+        runtime.height = height as f32;
+
+        Ok(runtime)
+    }
+
+    fn with_device_and_target(
+        wgs: WgsData,
+        viewport: Option<Viewport>,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface_configuration: wgpu::SurfaceConfiguration,
+        target: Target<'w>,
+    ) -> Result<Self> {
         #[cfg(not(target_arch = "wasm32"))]
         {
             device.push_error_scope(wgpu::ErrorFilter::Validation);
         }
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let (uniform, uniform_buffer, uniform_bind_group_layout) = setup_uniform(&device);
+
+        let param_values = default_param_values(&wgs);
 
-        let (uniform, uniform_buffer, uniform_bind_group_layout, uniform_bind_group) =
-            setup_uniform(&device);
+        let param_buffer = create_param_buffer(&device, &param_values);
+
+        let feedback = Feedback::new(
+            &device,
+            surface_configuration.format,
+            &uniform_bind_group_layout,
+            &uniform_buffer,
+            &param_buffer,
+        );
 
         let shader_vert = VERT_DEFAULT.to_owned();
 
-        let (texture_bind_groups, pipeline) = prepare_wgs(
+        let (textures, passes, pipeline) = prepare_wgs(
             &wgs,
+            &Library::default(),
             &device,
             &queue,
-            &sampler,
-            surface_configuration.format,
+            FEEDBACK_FORMAT,
             &shader_vert,
             &uniform_bind_group_layout,
         )?;
@@ -319,24 +543,27 @@ impl<'w> Runtime<'w> {
             #[cfg(not(target_arch = "wasm32"))]
             captured_callback: None,
             device,
+            feedback,
+            frame: None,
             height: 0.0,
             #[cfg(not(target_arch = "wasm32"))]
             is_capture_supported: surface_configuration
                 .usage
                 .contains(wgpu::TextureUsages::COPY_SRC),
             is_paused: false,
+            library: Library::default(),
+            param_buffer,
+            param_values,
+            passes,
             pipeline,
             queue,
-            sampler,
             shader_vert,
-            surface,
             surface_configuration,
-            surface_texture: None,
-            texture_bind_groups,
+            target,
             texture_view: None,
+            textures,
             time_instant: PausableInstant::now(),
             uniform,
-            uniform_bind_group,
             uniform_bind_group_layout,
             uniform_buffer,
             viewport,
@@ -363,15 +590,19 @@ impl<'w> Runtime<'w> {
     ///
     /// - Will return an error if [`Self::frame_start`] haven't been called first.
     pub fn frame_finish(&mut self) -> Result<()> {
-        if self.surface_texture.is_none() {
-            bail!("No actived wgpu::SurfaceTexture found.")
+        if self.frame.is_none() {
+            bail!("No actived frame found.")
         }
 
-        if let Some(surface_texture) = self.surface_texture.take() {
+        if let Some(frame) = self.frame.take() {
             #[cfg(not(target_arch = "wasm32"))]
             if let Some((viewport, callback)) = self.captured_callback.take() {
                 if self.is_capture_supported {
-                    let texture = &surface_texture.texture;
+                    let texture = match (&frame, &self.target) {
+                        (Frame::Surface(surface_texture), _) => &surface_texture.texture,
+                        (Frame::Offscreen, Target::Offscreen(texture)) => texture,
+                        (Frame::Offscreen, Target::Surface(_)) => unreachable!(),
+                    };
 
                     let size = texture.size();
 
@@ -386,7 +617,9 @@ impl<'w> Runtime<'w> {
                 }
             }
 
-            surface_texture.present();
+            if let Frame::Surface(surface_texture) = frame {
+                surface_texture.present();
+            }
         }
 
         Ok(())
@@ -400,21 +633,28 @@ impl<'w> Runtime<'w> {
     ///
     /// - Will return an error if [`Self::frame_finish`] haven't been called at the end of the last frame.
     pub fn frame_start(&mut self) -> Result<()> {
-        if self.surface_texture.is_some() {
-            bail!("Non-finished wgpu::SurfaceTexture found.")
+        if self.frame.is_some() {
+            bail!("Non-finished frame found.")
         }
 
-        let surface_texture = self.surface.get_current_texture()?;
+        let view_descriptor = wgpu::TextureViewDescriptor {
+            format: Some(self.surface_configuration.format),
+            ..wgpu::TextureViewDescriptor::default()
+        };
+
+        match &self.target {
+            Target::Offscreen(texture) => {
+                self.texture_view = Some(texture.create_view(&view_descriptor));
 
-        self.surface_texture = Some(surface_texture);
+                self.frame = Some(Frame::Offscreen);
+            }
+            Target::Surface(surface) => {
+                let surface_texture = surface.get_current_texture()?;
 
-        if let Some(surface_texture) = &self.surface_texture {
-            self.texture_view = Some(surface_texture.texture.create_view(
-                &wgpu::TextureViewDescriptor {
-                    format: Some(self.surface_configuration.format),
-                    ..wgpu::TextureViewDescriptor::default()
-                },
-            ));
+                self.texture_view = Some(surface_texture.texture.create_view(&view_descriptor));
+
+                self.frame = Some(Frame::Surface(surface_texture));
+            }
         }
 
         Ok(())
@@ -426,6 +666,11 @@ impl<'w> Runtime<'w> {
         self.is_capture_supported
     }
 
+    /// Returns whether the runtime renders into an offscreen texture instead of a surface.
+    pub fn is_offscreen(&self) -> bool {
+        matches!(self.target, Target::Offscreen(_))
+    }
+
     /// Returns whether the wgs rendering is currently paused.
     pub fn is_paused(&self) -> bool {
         self.is_paused
@@ -434,8 +679,14 @@ impl<'w> Runtime<'w> {
     /// The maximum number of textures that can be used.
     ///
     /// Depends on the [`wgpu::Limits::max_bind_groups`] of [`wgpu::Device`].
+    /// The uniforms take one bind group, and the outputs of the buffer passes one more if there are any.
     pub fn max_texture_count(&self) -> u32 {
-        self.device.limits().max_bind_groups
+        let reserved = 1 + self.passes.bind_group_layout.is_some() as u32;
+
+        self.device
+            .limits()
+            .max_bind_groups
+            .saturating_sub(reserved)
     }
 
     /// Pops an error scope from [`wgpu::Device`]. [Read more](wgpu::Device::pop_error_scope).
@@ -481,6 +732,100 @@ impl<'w> Runtime<'w> {
         self.captured_callback = Some((viewport.clone(), Box::new(f)));
     }
 
+    /// Request a capture on the given [`Viewport`] like [`Self::request_capture_image`],
+    /// and store the image as the thumbnail of wgs once captured.
+    ///
+    /// The encoding of the previous thumbnail is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn request_capture_thumbnail(&mut self, viewport: &Viewport) {
+        self.request_capture_image(viewport, |runtime, width, height, buffer| {
+            let Ok(mut thumbnail) = Texture::new(width, height, buffer) else {
+                return;
+            };
+
+            if let Some(previous) = runtime.wgs.thumbnail_ref() {
+                thumbnail.encoding = previous.encoding;
+            }
+
+            runtime.wgs.set_thumbnail(Some(thumbnail));
+        });
+    }
+
+    /// Renders a single frame described by the [`Snapshot`] and returns
+    /// the width, the height and the RGBA8 buffer of the image.
+    ///
+    /// The time and the mouse state of the snapshot override the current ones
+    /// and the runtime is resized to the size of the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// - Will return an error if the runtime is not an offscreen one. See [`Self::new_offscreen`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn render_snapshot(&mut self, snapshot: &Snapshot) -> Result<(u32, u32, Vec<u8>)> {
+        if !self.is_offscreen() {
+            bail!("Snapshots can only be rendered by an offscreen runtime.")
+        }
+
+        if self.surface_configuration.width != snapshot.width
+            || self.surface_configuration.height != snapshot.height
+        {
+            self.resize(snapshot.width as f32, snapshot.height as f32);
+        }
+
+        let was_paused = self.is_paused;
+
+        self.pause();
+
+        self.set_time(snapshot.time);
+
+        let resolution = self.resolution();
+
+        let flip = |position: [f32; 2]| [position[0], resolution[1] - position[1]];
+
+        self.uniform.cursor = flip(snapshot.cursor);
+        self.uniform.mouse_down = snapshot.mouse_down as u32;
+        self.uniform.mouse_press = flip(snapshot.mouse_press);
+        self.uniform.mouse_release = flip(snapshot.mouse_release);
+
+        self.frame_start()?;
+
+        self.render()?;
+
+        let viewport = self.viewport.clone().unwrap_or(Viewport {
+            width: snapshot.width as f32,
+            height: snapshot.height as f32,
+            max_depth: 1.0,
+            ..Viewport::default()
+        });
+
+        let image = match &self.target {
+            Target::Offscreen(texture) => {
+                self.capture_image(
+                    &viewport,
+                    snapshot.width,
+                    snapshot.height,
+                    texture.as_image_copy(),
+                )
+                .await
+            }
+            Target::Surface(_) => unreachable!(),
+        };
+
+        self.frame_finish()?;
+
+        if !was_paused {
+            self.resume();
+        }
+
+        image
+    }
+
+    /// Sets the elapsed time passed to wgs, in seconds.
+    pub fn set_time(&mut self, time: f32) {
+        self.time_instant
+            .set_elapsed(Duration::try_from_secs_f32(time).unwrap_or_default());
+    }
+
     /// Sets the [`Viewport`] for render wgs.
     pub fn set_viewport(&mut self, viewport: Option<Viewport>) {
         self.viewport = viewport;
@@ -488,7 +833,7 @@ impl<'w> Runtime<'w> {
 
     #[cfg(not(target_arch = "wasm32"))]
     async fn capture_image(
-        &mut self,
+        &self,
         viewport: &Viewport,
         raw_width: u32,
         raw_height: u32,
@@ -498,6 +843,12 @@ impl<'w> Runtime<'w> {
             bail!("Capture is not supported.")
         }
 
+        let format = self.surface_configuration.format;
+
+        if format.block_copy_size(None) != Some(DATA_PER_PIXEL * U8_SIZE) {
+            bail!("Capture is not supported for texture format {:?}.", format)
+        }
+
         let align_width = align_up(
             raw_width * DATA_PER_PIXEL * U8_SIZE,
             wgpu::COPY_BYTES_PER_ROW_ALIGNMENT,
@@ -537,6 +888,7 @@ impl<'w> Runtime<'w> {
 
         let buffer = view_into_buffer(
             &self.device,
+            format,
             viewport,
             raw_width,
             raw_height,
@@ -549,6 +901,64 @@ impl<'w> Runtime<'w> {
 
         Ok((width, height, buffer))
     }
+
+    fn packed_params(&self) -> Vec<u8> {
+        let values = self
+            .param_values
+            .iter()
+            .map(|(_, value)| *value)
+            .collect::<Vec<_>>();
+
+        pack_params(&values)
+    }
+
+    /// Keeps the current values of the parameters still in wgs with the same type,
+    /// and recreates the buffer if it's too small to hold them.
+    fn prepare_params(&mut self) {
+        let mut param_values = default_param_values(&self.wgs);
+
+        for (param, (_, value)) in self.wgs.params_ref().iter().zip(&mut param_values) {
+            let current = self
+                .param_values
+                .iter()
+                .find(|(name, _)| *name == param.name)
+                .and_then(|(_, current)| param.check(*current).ok());
+
+            if let Some(current) = current {
+                *value = current;
+            }
+        }
+
+        self.param_values = param_values;
+
+        if (self.param_buffer.size() as usize) < self.packed_params().len() {
+            self.param_buffer = create_param_buffer(&self.device, &self.param_values);
+
+            self.feedback.clear();
+        }
+    }
+
+    /// Returns the size of the textures the image and the buffer passes are rendered into.
+    fn render_size(&self) -> (u32, u32) {
+        let resolution = self.resolution();
+
+        (resolution[0] as u32, resolution[1] as u32)
+    }
+
+    fn resolution(&self) -> [f32; 2] {
+        if let Some(viewport) = &self.viewport {
+            [viewport.width, viewport.height]
+        } else {
+            [self.width, self.height]
+        }
+    }
+}
+
+impl TextureBinding {
+    pub fn create_view(&self) -> wgpu::TextureView {
+        self.texture
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -556,7 +966,7 @@ fn align_up(num: u32, align: u32) -> u32 {
     (num + align - 1) & !(align - 1)
 }
 
-fn build_pipeline(
+pub fn build_pipeline(
     shader_frag: &str,
     shader_vert: &str,
     bind_group_layouts: &[&wgpu::BindGroupLayout],
@@ -604,23 +1014,41 @@ fn build_pipeline(
     Ok(pipeline)
 }
 
-fn create_texture(
+fn create_param_buffer(
     device: &wgpu::Device,
-    queue: &wgpu::Queue,
-    sampler: &wgpu::Sampler,
-    width: u32,
-    height: u32,
-    buffer: &[u8],
-) -> (wgpu::BindGroupLayout, wgpu::BindGroup) {
-    let texture_size = wgpu::Extent3d {
-        width,
-        height,
-        depth_or_array_layers: 1,
+    param_values: &[(String, ParamValue)],
+) -> wgpu::Buffer {
+    let values = param_values
+        .iter()
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Param Buffer"),
+        contents: &pack_params(&values),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+fn create_texture(device: &wgpu::Device, queue: &wgpu::Queue, texture: &Texture) -> TextureBinding {
+    let mip_levels = match texture.sampler.mipmap_filter {
+        Some(_) => generate_mip_levels(texture.width, texture.height, &texture.data),
+        None => vec![(
+            texture.width,
+            texture.height,
+            Cow::Borrowed(&texture.data[..]),
+        )],
     };
 
+    let sampler = device.create_sampler(&sampler_descriptor(&texture.sampler));
+
     let texture = device.create_texture(&wgpu::TextureDescriptor {
-        size: texture_size,
-        mip_level_count: 1,
+        size: wgpu::Extent3d {
+            width: texture.width,
+            height: texture.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: mip_levels.len() as u32,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -629,21 +1057,27 @@ fn create_texture(
         view_formats: &[wgpu::TextureFormat::Rgba8UnormSrgb],
     });
 
-    queue.write_texture(
-        wgpu::ImageCopyTexture {
-            texture: &texture,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-            aspect: wgpu::TextureAspect::All,
-        },
-        buffer,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * width),
-            rows_per_image: Some(height),
-        },
-        texture_size,
-    );
+    for (mip_level, (width, height, buffer)) in (0..).zip(&mip_levels) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            buffer,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(*height),
+            },
+            wgpu::Extent3d {
+                width: *width,
+                height: *height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
 
     let texture_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -685,7 +1119,57 @@ fn create_texture(
         label: Some("Bind Group"),
     });
 
-    (texture_bind_group_layout, texture_bind_group)
+    TextureBinding {
+        bind_group: texture_bind_group,
+        bind_group_layout: texture_bind_group_layout,
+        sampler,
+        texture,
+    }
+}
+
+fn default_param_values(wgs: &WgsData) -> Vec<(String, ParamValue)> {
+    wgs.params_ref()
+        .iter()
+        .map(|param| (param.name.clone(), param.default_value()))
+        .collect()
+}
+
+/// Downsamples the 8bit RGBA pixel data with a box filter until it's 1x1.
+/// An empty texture has no other level.
+///
+/// Returns the size and the data of every mip level, starting from the original one.
+fn generate_mip_levels(width: u32, height: u32, buffer: &[u8]) -> Vec<(u32, u32, Cow<'_, [u8]>)> {
+    let mut mip_levels = vec![(width, height, Cow::Borrowed(buffer))];
+
+    while let Some((width, height, buffer)) = mip_levels
+        .last()
+        .filter(|(w, h, _)| *w > 0 && *h > 0 && (*w > 1 || *h > 1))
+    {
+        let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+        let mut next = Vec::with_capacity((next_width * next_height * 4) as usize);
+
+        for y in 0..next_height {
+            for x in 0..next_width {
+                let texels = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| {
+                    let x = (x * 2 + dx).min(width - 1);
+                    let y = (y * 2 + dy).min(height - 1);
+                    ((y * width + x) * 4) as usize
+                });
+
+                for channel in 0..4 {
+                    let sum: u32 = texels
+                        .iter()
+                        .map(|texel| buffer[texel + channel] as u32)
+                        .sum();
+                    next.push(((sum + 2) / 4) as u8);
+                }
+            }
+        }
+
+        mip_levels.push((next_width, next_height, Cow::Owned(next)));
+    }
+
+    mip_levels
 }
 
 async fn init_adapter<'w>(
@@ -701,7 +1185,7 @@ async fn init_adapter<'w>(
     {
         let adapter_features = adapter.features();
 
-        let surface_configuration = init_surface_configuration(&surface, &adapter);
+        let surface_configuration = init_surface_configuration(surface, &adapter);
 
         let (device, queue) = adapter
             .request_device(
@@ -724,17 +1208,55 @@ async fn init_adapter<'w>(
     }
 }
 
+async fn init_adapter_offscreen(instance: &wgpu::Instance) -> Result<(wgpu::Device, wgpu::Queue)> {
+    let mut adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await;
+
+    if adapter.is_none() {
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                force_fallback_adapter: true,
+                ..wgpu::RequestAdapterOptions::default()
+            })
+            .await;
+    }
+
+    if let Some(adapter) = adapter {
+        let adapter_features = adapter.features();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Device Descriptor"),
+                    required_features: adapter_features & wgpu::Features::default(),
+                    #[cfg(target_arch = "wasm32")]
+                    required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                        .using_resolution(adapter.limits()),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    required_limits: wgpu::Limits::downlevel_defaults()
+                        .using_resolution(adapter.limits()),
+                    memory_hints: wgpu::MemoryHints::default(),
+                },
+                None,
+            )
+            .await?;
+
+        Ok((device, queue))
+    } else {
+        bail!("No adapters are found, including the fallback adapter.")
+    }
+}
+
 fn init_instance() -> wgpu::Instance {
     let backends = wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all);
 
-    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
         backends,
         flags: wgpu::InstanceFlags::debugging(),
         dx12_shader_compiler: wgpu::Dx12Compiler::default(),
         gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
-    });
-
-    instance
+    })
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -761,7 +1283,7 @@ fn init_surface_configuration(
     surface: &wgpu::Surface,
     adapter: &wgpu::Adapter,
 ) -> wgpu::SurfaceConfiguration {
-    let swapchain_capabilities = surface.get_capabilities(&adapter);
+    let swapchain_capabilities = surface.get_capabilities(adapter);
 
     let format = swapchain_capabilities.formats[0];
 
@@ -769,7 +1291,7 @@ fn init_surface_configuration(
         .usages
         .contains(wgpu::TextureUsages::COPY_SRC);
 
-    let config = if let Some(mut config) = surface.get_default_config(adapter, 0, 0) {
+    if let Some(mut config) = surface.get_default_config(adapter, 0, 0) {
         if is_capture_supported {
             config.usage |= wgpu::TextureUsages::COPY_SRC;
         }
@@ -792,55 +1314,53 @@ fn init_surface_configuration(
             alpha_mode: wgpu::CompositeAlphaMode::Auto,
             view_formats: vec![format],
         }
-    };
-
-    config
+    }
 }
 
 fn prepare_wgs(
     wgs: &WgsData,
+    library: &Library,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    sampler: &wgpu::Sampler,
     format: wgpu::TextureFormat,
     shader_vert: &str,
     uniform_bind_group_layout: &wgpu::BindGroupLayout,
-) -> Result<(
-    Vec<(wgpu::BindGroupLayout, wgpu::BindGroup)>,
-    wgpu::RenderPipeline,
-)> {
-    let texture_bind_groups = prepare_wgs_textures(wgs, device, queue, sampler);
+) -> Result<(Vec<TextureBinding>, Passes, wgpu::RenderPipeline)> {
+    let textures = prepare_wgs_textures(wgs, device, queue);
+
+    let passes = Passes::new(wgs, library, device, shader_vert, uniform_bind_group_layout)?;
 
     let pipeline = prepare_wgs_pipeline(
-        wgs,
+        &wgs.shader_frag_with_library(library)?.0,
         device,
         format,
         shader_vert,
-        &texture_bind_groups,
+        &textures,
+        &passes,
         uniform_bind_group_layout,
     )?;
 
-    Ok((texture_bind_groups, pipeline))
+    Ok((textures, passes, pipeline))
 }
 
 fn prepare_wgs_pipeline(
-    wgs: &WgsData,
+    shader_frag: &str,
     device: &wgpu::Device,
     format: wgpu::TextureFormat,
     shader_vert: &str,
-    texture_bind_groups: &Vec<(wgpu::BindGroupLayout, wgpu::BindGroup)>,
+    textures: &[TextureBinding],
+    passes: &Passes,
     uniform_bind_group_layout: &wgpu::BindGroupLayout,
 ) -> Result<wgpu::RenderPipeline> {
     let mut bind_group_layouts = vec![uniform_bind_group_layout];
-    for (layout, _) in texture_bind_groups {
-        bind_group_layouts.push(layout);
+    for texture in textures {
+        bind_group_layouts.push(&texture.bind_group_layout);
     }
-
-    let shader_frag = concat_shader_frag(&wgs.frag(), wgs.textures_ref().len());
+    bind_group_layouts.extend(passes.bind_group_layout.as_ref());
 
     let pipeline = build_pipeline(
-        &shader_frag,
-        &shader_vert,
+        shader_frag,
+        shader_vert,
         &bind_group_layouts,
         device,
         format,
@@ -853,28 +1373,40 @@ fn prepare_wgs_textures(
     wgs: &WgsData,
     device: &wgpu::Device,
     queue: &wgpu::Queue,
-    sampler: &wgpu::Sampler,
-) -> Vec<(wgpu::BindGroupLayout, wgpu::BindGroup)> {
-    let textures = wgs
-        .textures_ref()
-        .iter()
-        .map(|texture| (texture.width, texture.height, &texture.data))
-        .collect::<Vec<(u32, u32, &Vec<u8>)>>();
-
-    textures
+) -> Vec<TextureBinding> {
+    wgs.textures_ref()
         .iter()
-        .map(|(width, height, data)| create_texture(device, queue, sampler, *width, *height, &data))
+        .map(|texture| create_texture(device, queue, texture))
         .collect()
 }
 
-fn setup_uniform(
-    device: &wgpu::Device,
-) -> (
-    Uniform,
-    wgpu::Buffer,
-    wgpu::BindGroupLayout,
-    wgpu::BindGroup,
-) {
+fn sampler_descriptor(sampler: &TextureSampler) -> wgpu::SamplerDescriptor<'static> {
+    let address_mode = match sampler.address_mode {
+        AddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        AddressMode::Repeat => wgpu::AddressMode::Repeat,
+        AddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+    };
+
+    let filter_mode = |filter| match filter {
+        FilterMode::Nearest => wgpu::FilterMode::Nearest,
+        FilterMode::Linear => wgpu::FilterMode::Linear,
+    };
+
+    wgpu::SamplerDescriptor {
+        label: Some("Texture Sampler"),
+        address_mode_u: address_mode,
+        address_mode_v: address_mode,
+        address_mode_w: address_mode,
+        mag_filter: filter_mode(sampler.mag_filter),
+        min_filter: filter_mode(sampler.min_filter),
+        mipmap_filter: sampler
+            .mipmap_filter
+            .map_or(wgpu::FilterMode::Nearest, filter_mode),
+        ..wgpu::SamplerDescriptor::default()
+    }
+}
+
+fn setup_uniform(device: &wgpu::Device) -> (Uniform, wgpu::Buffer, wgpu::BindGroupLayout) {
     let uniform = Uniform::default();
 
     let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -886,53 +1418,81 @@ fn setup_uniform(
     let uniform_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Uniform Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
         });
 
-    let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Uniform Bind Group"),
-        layout: &uniform_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: uniform_buffer.as_entire_binding(),
-        }],
-    });
-
-    (
-        uniform,
-        uniform_buffer,
-        uniform_bind_group_layout,
-        uniform_bind_group,
-    )
+    (uniform, uniform_buffer, uniform_bind_group_layout)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn trim_image_buffer(viewport: &Viewport, align_width: usize, buffer: &[u8]) -> Vec<u8> {
+fn trim_image_buffer(
+    format: wgpu::TextureFormat,
+    viewport: &Viewport,
+    align_width: usize,
+    buffer: &[u8],
+) -> Vec<u8> {
     let x = viewport.x as usize;
     let width = viewport.width as usize;
     let height = viewport.height as usize;
 
     let mut output = Vec::with_capacity(width * height);
 
+    let is_bgra = matches!(
+        format,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+    );
+
     let pad_before_per_row = x * DATA_PER_PIXEL as usize * U8_SIZE as usize;
     let len_per_row = width * DATA_PER_PIXEL as usize * U8_SIZE as usize;
 
     for chunk in buffer.chunks(align_width) {
         for chunk in chunk[pad_before_per_row..pad_before_per_row + len_per_row].chunks(4) {
-            // Convert BGRA8 to RGBA8
-            output.push(chunk[2]);
-            output.push(chunk[1]);
-            output.push(chunk[0]);
-            output.push(chunk[3]);
+            if is_bgra {
+                // Convert BGRA8 to RGBA8
+                output.push(chunk[2]);
+                output.push(chunk[1]);
+                output.push(chunk[0]);
+                output.push(chunk[3]);
+            } else {
+                output.extend_from_slice(chunk);
+            }
         }
     }
 
@@ -942,6 +1502,7 @@ fn trim_image_buffer(viewport: &Viewport, align_width: usize, buffer: &[u8]) ->
 #[cfg(not(target_arch = "wasm32"))]
 async fn view_into_buffer(
     device: &wgpu::Device,
+    format: wgpu::TextureFormat,
     viewport: &Viewport,
     _raw_width: u32,
     raw_height: u32,
@@ -955,10 +1516,11 @@ async fn view_into_buffer(
 
     device.poll(wgpu::Maintain::Wait);
 
-    if let std::result::Result::Ok(_) = receiver.await {
+    if receiver.await.is_ok() {
         let buffer_view = slice.get_mapped_range();
 
         let buffer = trim_image_buffer(
+            format,
             viewport,
             buffer_view.len() / raw_height as usize,
             &buffer_view,
@@ -972,3 +1534,25 @@ async fn view_into_buffer(
         bail!("Failed to map the buffer.")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mip_levels_are_halved_down_to_one_texel() {
+        let buffer: Vec<u8> = (0..3 * 2).flat_map(|texel| [texel * 10; 4]).collect();
+        let mip_levels = generate_mip_levels(3, 2, &buffer);
+        let sizes: Vec<_> = mip_levels.iter().map(|(w, h, _)| (*w, *h)).collect();
+
+        assert_eq!(sizes, [(3, 2), (1, 1)]);
+        assert_eq!(*mip_levels[1].2, [20; 4]);
+    }
+
+    #[test]
+    fn empty_texture_has_one_mip_level() {
+        for (width, height) in [(0, 0), (0, 4), (4, 0)] {
+            assert_eq!(generate_mip_levels(width, height, &[]).len(), 1);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::runtime::Runtime;
+use anyhow::Result;
+use std::io;
+use wgs_core::WgsData;
+
+/// Describes a single frame of wgs to be rendered without a window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// The width of the image in pixels.
+    pub width: u32,
+    /// The height of the image in pixels.
+    pub height: u32,
+    /// The elapsed time passed to wgs, in seconds.
+    pub time: f32,
+    /// The cursor position in pixels, with the origin at the top left corner of the image.
+    pub cursor: [f32; 2],
+    /// Whether the left button of the mouse is down.
+    pub mouse_down: bool,
+    /// The cursor position in pixels when the left button was pressed.
+    pub mouse_press: [f32; 2],
+    /// The cursor position in pixels when the left button was released.
+    pub mouse_release: [f32; 2],
+}
+
+impl Snapshot {
+    /// Creates a snapshot of the given size at time zero, with the mouse untouched.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            time: 0.0,
+            cursor: [0.0, 0.0],
+            mouse_down: false,
+            mouse_press: [0.0, 0.0],
+            mouse_release: [0.0, 0.0],
+        }
+    }
+}
+
+/// Renders wgs as described by the [`Snapshot`] with an offscreen runtime and returns
+/// the width, the height and the RGBA8 buffer of the image.
+pub async fn render_snapshot(wgs: WgsData, snapshot: &Snapshot) -> Result<(u32, u32, Vec<u8>)> {
+    let mut runtime = Runtime::new_offscreen(
+        wgs,
+        None,
+        snapshot.width,
+        snapshot.height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    )
+    .await?;
+
+    runtime.render_snapshot(snapshot).await
+}
+
+/// Renders wgs as described by the [`Snapshot`] and writes the image to the writer in PNG format.
+pub async fn render_snapshot_png(
+    wgs: WgsData,
+    snapshot: &Snapshot,
+    writer: impl io::Write,
+) -> Result<()> {
+    let (width, height, buffer) = render_snapshot(wgs, snapshot).await?;
+
+    write_png(writer, width, height, &buffer)
+}
+
+/// Writes a RGBA8 buffer to the writer in PNG format.
+pub fn write_png(writer: impl io::Write, width: u32, height: u32, buffer: &[u8]) -> Result<()> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(buffer)?;
+    writer.finish()?;
+
+    Ok(())
+}
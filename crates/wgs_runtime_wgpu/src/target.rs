@@ -0,0 +1,64 @@
+/// The destination the runtime renders into.
+pub enum Target<'w> {
+    /// An internally owned texture, used when rendering without a window.
+    Offscreen(wgpu::Texture),
+    /// A presentable surface, usually backed by a window or a canvas.
+    Surface(wgpu::Surface<'w>),
+}
+
+/// The texture acquired for the frame currently being rendered.
+pub enum Frame {
+    Offscreen,
+    Surface(wgpu::SurfaceTexture),
+}
+
+impl Target<'_> {
+    /// Applies the configuration to the target, recreating the offscreen texture if needed.
+    pub fn configure(&mut self, device: &wgpu::Device, configuration: &wgpu::SurfaceConfiguration) {
+        match self {
+            Self::Offscreen(texture) => {
+                *texture = create_offscreen_texture(device, configuration);
+            }
+            Self::Surface(surface) => {
+                surface.configure(device, configuration);
+            }
+        }
+    }
+}
+
+pub fn create_offscreen_texture(
+    device: &wgpu::Device,
+    configuration: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Texture"),
+        size: wgpu::Extent3d {
+            width: configuration.width.max(1),
+            height: configuration.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: configuration.format,
+        usage: configuration.usage,
+        view_formats: &configuration.view_formats,
+    })
+}
+
+pub fn offscreen_configuration(
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::SurfaceConfiguration {
+    wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        format,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::AutoVsync,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![format],
+    }
+}
@@ -13,45 +13,54 @@ pub struct WebRuntime {
 
 #[wasm_bindgen(js_class = Runtime)]
 impl WebRuntime {
-    pub fn add_texture(&mut self, width: u32, height: u32, buffer: &[u8]) {
-        self.inner.add_texture(width, height, buffer.to_vec());
+    pub fn add_texture(&mut self, width: u32, height: u32, buffer: &[u8]) -> Result<(), JsValue> {
+        self.inner
+            .add_texture(width, height, buffer.to_vec())
+            .map_err(to_js_value)
     }
 
-    pub fn change_texture(&mut self, index: usize, width: u32, height: u32, buffer: &[u8]) {
+    pub fn change_texture(
+        &mut self,
+        index: usize,
+        width: u32,
+        height: u32,
+        buffer: &[u8],
+    ) -> Result<(), JsValue> {
         self.inner
-            .change_texture(index, width, height, buffer.to_vec());
+            .change_texture(index, width, height, buffer.to_vec())
+            .map_err(to_js_value)
     }
 
-    pub fn compile(&mut self) {
-        self.inner.compile().unwrap();
+    pub fn compile(&mut self) -> Result<(), JsValue> {
+        self.inner.compile().map_err(to_js_value)
     }
 
     pub fn is_paused(&self) -> bool {
         self.inner.is_paused()
     }
 
-    pub fn load(&mut self, raw_wgs: &[u8]) {
+    pub fn load(&mut self, raw_wgs: &[u8]) -> Result<(), JsValue> {
         let mut cursor = Cursor::new(raw_wgs);
 
-        let wgs = WgsData::load(&mut cursor).unwrap();
+        let wgs = WgsData::load(&mut cursor).map_err(to_js_value)?;
 
-        self.inner.load(wgs);
+        self.inner.load(wgs).map_err(to_js_value)
     }
 
     pub fn pause(&mut self) {
         self.inner.pause();
     }
 
-    pub fn remove_texture(&mut self, index: usize) {
-        self.inner.remove_texture(index);
+    pub fn remove_texture(&mut self, index: usize) -> Result<(), JsValue> {
+        self.inner.remove_texture(index).map_err(to_js_value)
     }
 
-    pub fn render(&mut self) {
-        self.inner.frame_start().unwrap();
+    pub fn render(&mut self) -> Result<(), JsValue> {
+        self.inner.frame_start().map_err(to_js_value)?;
 
-        self.inner.render();
+        self.inner.render().map_err(to_js_value)?;
 
-        self.inner.frame_finish().unwrap();
+        self.inner.frame_finish().map_err(to_js_value)
     }
 
     pub fn resize(&mut self, width: f32, height: f32) {
@@ -103,7 +112,12 @@ pub fn setup(canvas: HtmlCanvasElement) -> Promise {
 
                 Ok(runtime.into())
             }
-            Err(err) => Err(err.to_string().into()),
+            Err(err) => Err(to_js_value(err)),
         }
     })
 }
+
+/// Turns an error into a JS string, so that it's thrown instead of panicking.
+fn to_js_value(err: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}